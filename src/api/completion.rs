@@ -99,6 +99,13 @@ pub struct ToolCall {
 
     /// Function details
     pub function: FunctionCall,
+
+    /// Whether this call's `arguments` are known-complete. Only set by
+    /// [`crate::api::streaming::StreamAccumulator::into_message`] for a
+    /// tool call assembled from stream deltas; absent (and omitted from the
+    /// wire format) everywhere else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete: Option<bool>,
 }
 
 /// Function call details
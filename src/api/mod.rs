@@ -7,6 +7,9 @@ pub mod streaming;
 
 pub use completion::{
     Choice, CompletionRequest, CompletionResponse, ContentPart, FunctionCall, FunctionDefinition,
-    ImageUrl, Message, MessageContent, Tool, ToolCall, ToolChoice, Usage,
+    ImageUrl, Message, MessageContent, Tool, ToolCall, ToolChoice, ToolChoiceFunction, Usage,
+};
+pub use streaming::{
+    parse_sse_id, parse_sse_line, parse_sse_retry, FunctionDelta, StreamAccumulator, StreamChoice, StreamChunk,
+    StreamDelta, ToolCallDelta,
 };
-pub use streaming::{parse_sse_line, StreamAccumulator, StreamChoice, StreamChunk, StreamDelta};
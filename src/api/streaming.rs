@@ -5,6 +5,7 @@
 use crate::api::completion::{Message, MessageContent, ToolCall, Usage};
 use crate::error::{LlmaoError, Result};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// A streaming chunk from the API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,6 +127,95 @@ pub struct ToolCallAccumulator {
     pub call_type: String,
     pub name: String,
     pub arguments: String,
+
+    /// Set once the enclosing choice reports a `tool_calls` finish reason.
+    /// Still `false` for a call whose deltas were cut off mid-stream.
+    pub complete: bool,
+}
+
+impl ToolCallAccumulator {
+    /// Normalize and validate a tool call once its delta stream is
+    /// complete: synthesize an id if the provider never sent one, and
+    /// confirm the concatenated `arguments` fragments form valid JSON so
+    /// callers don't discover a malformed tool call deep inside their own
+    /// `json.loads`.
+    pub fn finalize(mut self, index: u32) -> Result<Self> {
+        if self.id.is_empty() {
+            self.id = format!("call_{}", index);
+        }
+
+        serde_json::from_str::<serde_json::Value>(&self.arguments).map_err(|e| {
+            LlmaoError::Stream(format!(
+                "Tool call '{}' accumulated invalid JSON arguments: {}",
+                self.name, e
+            ))
+        })?;
+
+        Ok(self)
+    }
+}
+
+/// A tool call as seen mid-stream, before its arguments are necessarily
+/// valid JSON.
+#[derive(Debug, Clone)]
+pub struct PartialToolCall {
+    pub index: usize,
+    pub id: String,
+    pub name: String,
+
+    /// Best-effort parse of [`repair_json`] applied to the arguments
+    /// accumulated so far
+    pub arguments: Result<serde_json::Value>,
+
+    /// Set once the enclosing choice reports a `tool_calls` finish reason
+    pub complete: bool,
+}
+
+/// Best-effort repair of a truncated JSON document: closes an unterminated
+/// string, strips a trailing comma, and balances any braces/brackets left
+/// open by the cut-off point. Not a general JSON parser — just enough to
+/// turn the in-flight `arguments` of a streaming tool call into something
+/// `serde_json` can parse before the provider has sent the rest of it.
+pub fn repair_json(input: &str) -> String {
+    let mut repaired = String::with_capacity(input.len() + 8);
+    let mut stack = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for ch in input.chars() {
+        repaired.push(ch);
+
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match ch {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => stack.push('}'),
+            '[' if !in_string => stack.push(']'),
+            '}' | ']' if !in_string => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    if in_string {
+        repaired.push('"');
+    }
+
+    while matches!(repaired.trim_end().chars().next_back(), Some(',')) {
+        let trimmed = repaired.trim_end();
+        repaired.truncate(trimmed.len() - 1);
+    }
+
+    for closer in stack.into_iter().rev() {
+        repaired.push(closer);
+    }
+
+    repaired
 }
 
 impl StreamAccumulator {
@@ -197,12 +287,52 @@ impl StreamAccumulator {
             // Store finish reason
             if let Some(reason) = &choice.finish_reason {
                 self.finish_reason = Some(reason.clone());
+
+                if reason == "tool_calls" {
+                    for tc in &mut self.tool_calls {
+                        tc.complete = true;
+                    }
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Snapshot the tool calls accumulated so far, repairing truncated
+    /// `arguments` JSON on a best-effort basis so a responsive agent loop
+    /// can act on a call before the stream (or the call itself) has
+    /// finished. Check `complete` before treating a call as final.
+    pub fn partial_tool_calls(&self) -> Vec<PartialToolCall> {
+        self.tool_calls
+            .iter()
+            .enumerate()
+            .map(|(index, tc)| {
+                let id = if tc.id.is_empty() {
+                    format!("call_{}", index)
+                } else {
+                    tc.id.clone()
+                };
+
+                let arguments = serde_json::from_str::<serde_json::Value>(&repair_json(&tc.arguments))
+                    .map_err(|e| {
+                        LlmaoError::Stream(format!(
+                            "Tool call '{}' arguments could not be repaired into valid JSON: {}",
+                            tc.name, e
+                        ))
+                    });
+
+                PartialToolCall {
+                    index,
+                    id,
+                    name: tc.name.clone(),
+                    arguments,
+                    complete: tc.complete,
+                }
+            })
+            .collect()
+    }
+
     /// Convert to a final Message
     pub fn into_message(self) -> Message {
         let tool_calls = if self.tool_calls.is_empty() {
@@ -218,6 +348,7 @@ impl StreamAccumulator {
                             name: tc.name,
                             arguments: tc.arguments,
                         },
+                        complete: Some(tc.complete),
                     })
                     .collect(),
             )
@@ -262,6 +393,23 @@ pub fn parse_sse_line(line: &str) -> Result<Option<StreamChunk>> {
     Ok(None)
 }
 
+/// Parse an SSE `id:` field, tracking the last event id seen so a dropped
+/// connection can be resumed with a `Last-Event-ID` header.
+pub fn parse_sse_id(line: &str) -> Option<String> {
+    let id = line.trim().strip_prefix("id:")?.trim();
+    if id.is_empty() {
+        None
+    } else {
+        Some(id.to_string())
+    }
+}
+
+/// Parse an SSE `retry:` field: the server-suggested reconnect interval, in milliseconds.
+pub fn parse_sse_retry(line: &str) -> Option<Duration> {
+    let millis = line.trim().strip_prefix("retry:")?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_millis(millis))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,6 +429,74 @@ mod tests {
         assert!(parse_sse_line(line).unwrap().is_none());
     }
 
+    #[test]
+    fn test_repair_json_closes_unterminated_string_and_braces() {
+        let partial = r#"{"city": "San Fran"#;
+        assert_eq!(repair_json(partial), r#"{"city": "San Fran"}"#);
+    }
+
+    #[test]
+    fn test_repair_json_strips_trailing_comma_and_balances_brackets() {
+        let partial = r#"{"items": ["a", "b","#;
+        assert_eq!(repair_json(partial), r#"{"items": ["a", "b"]}"#);
+    }
+
+    #[test]
+    fn test_repair_json_leaves_complete_json_untouched() {
+        let complete = r#"{"a": 1}"#;
+        assert_eq!(repair_json(complete), complete);
+    }
+
+    #[test]
+    fn test_partial_tool_calls_repairs_truncated_arguments_and_tracks_complete() {
+        let mut acc = StreamAccumulator::new();
+
+        let chunk = StreamChunk {
+            id: "test".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1,
+            model: "gpt-4".to_string(),
+            choices: vec![StreamChoice {
+                index: 0,
+                delta: StreamDelta {
+                    role: None,
+                    content: None,
+                    tool_calls: Some(vec![ToolCallDelta {
+                        index: 0,
+                        id: Some("call_1".to_string()),
+                        call_type: Some("function".to_string()),
+                        function: Some(FunctionDelta {
+                            name: Some("get_weather".to_string()),
+                            arguments: Some(r#"{"city": "Berlin"#.to_string()),
+                        }),
+                    }]),
+                },
+                finish_reason: None,
+            }],
+            usage: None,
+        };
+        acc.process_chunk(&chunk).unwrap();
+
+        let partial = acc.partial_tool_calls();
+        assert_eq!(partial.len(), 1);
+        assert!(!partial[0].complete);
+        let args = partial[0].arguments.as_ref().unwrap();
+        assert_eq!(args["city"], "Berlin");
+    }
+
+    #[test]
+    fn test_parse_sse_id() {
+        assert_eq!(parse_sse_id("id: evt-123"), Some("evt-123".to_string()));
+        assert_eq!(parse_sse_id("id:"), None);
+        assert_eq!(parse_sse_id("data: {}"), None);
+    }
+
+    #[test]
+    fn test_parse_sse_retry() {
+        assert_eq!(parse_sse_retry("retry: 2500"), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_sse_retry("data: {}"), None);
+    }
+
     #[test]
     fn test_stream_accumulator() {
         let mut acc = StreamAccumulator::new();
@@ -0,0 +1,546 @@
+//! Anthropic Messages API backend
+//!
+//! Anthropic's wire format diverges from OpenAI's in three ways this
+//! backend bridges: the system prompt is a top-level field rather than a
+//! message with `role: "system"`, content is always an array of typed
+//! blocks, and tool calls/results are `tool_use`/`tool_result` blocks
+//! embedded in that array instead of a separate `tool_calls` field. Its
+//! streaming events are also their own `message_start`/`content_block_delta`/
+//! `message_delta` sequence rather than OpenAI-style chunk objects.
+
+use crate::api::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, FunctionCall, FunctionDelta, Message,
+    MessageContent, StreamChoice, StreamChunk, StreamDelta, ToolCall, ToolCallDelta, ToolChoice, Usage,
+};
+use crate::backend::ProviderBackend;
+use crate::config::ProviderConfig;
+use crate::error::Result;
+use serde_json::json;
+
+/// Default max_tokens sent to Anthropic when the request doesn't set one
+/// (Anthropic, unlike OpenAI, requires this field)
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Backend for Anthropic's Messages API
+pub struct AnthropicBackend;
+
+impl ProviderBackend for AnthropicBackend {
+    fn build_url(&self, base_url: &str, _model: &str) -> String {
+        format!("{}/v1/messages", base_url.trim_end_matches('/'))
+    }
+
+    fn build_body(&self, request: &CompletionRequest, config: &ProviderConfig) -> serde_json::Value {
+        let mut messages = request.messages.clone();
+        let system = take_leading_system_prompt(&mut messages);
+
+        let mut body = json!({
+            "model": request.model,
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": messages.iter().map(to_anthropic_message).collect::<Vec<_>>(),
+        });
+
+        let obj = body.as_object_mut().expect("body is always an object");
+        if let Some(system) = system {
+            obj.insert("system".to_string(), json!(system));
+        }
+        if let Some(temperature) = request.temperature {
+            obj.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(stream) = request.stream {
+            obj.insert("stream".to_string(), json!(stream));
+        }
+        if let Some(tools) = &request.tools {
+            let tools = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.function.name,
+                        "description": tool.function.description,
+                        "input_schema": tool.function.parameters.clone().unwrap_or(json!({"type": "object", "properties": {}})),
+                    })
+                })
+                .collect::<Vec<_>>();
+            obj.insert("tools".to_string(), json!(tools));
+        }
+        if let Some(tool_choice) = &request.tool_choice {
+            obj.insert("tool_choice".to_string(), to_anthropic_tool_choice(tool_choice));
+        }
+
+        config.apply_param_mappings(&mut body);
+        body
+    }
+
+    fn parse_response(&self, raw: serde_json::Value) -> Result<CompletionResponse> {
+        let id = raw
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let model = raw
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let stop_reason = raw.get("stop_reason").and_then(|v| v.as_str());
+
+        let blocks = raw
+            .get("content")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in &blocks {
+            match block.get("type").and_then(|v| v.as_str()) {
+                Some("text") => {
+                    text.push_str(block.get("text").and_then(|v| v.as_str()).unwrap_or(""));
+                }
+                Some("tool_use") => {
+                    tool_calls.push(ToolCall {
+                        id: block
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: block
+                                .get("name")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .to_string(),
+                            arguments: block
+                                .get("input")
+                                .map(|v| v.to_string())
+                                .unwrap_or_else(|| "{}".to_string()),
+                        },
+                        complete: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let message = Message {
+            role: "assistant".to_string(),
+            content: MessageContent::Text(text),
+            name: None,
+            tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+            tool_call_id: None,
+        };
+
+        let usage = raw.get("usage").map(|usage| {
+            let input_tokens = usage.get("input_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            let output_tokens = usage.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            Usage {
+                prompt_tokens: input_tokens,
+                completion_tokens: output_tokens,
+                total_tokens: input_tokens + output_tokens,
+            }
+        });
+
+        Ok(CompletionResponse {
+            id,
+            object: "chat.completion".to_string(),
+            created: 0,
+            model,
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: stop_reason.map(map_stop_reason),
+            }],
+            usage,
+        })
+    }
+
+    fn parse_stream_event(&self, line: &str) -> Option<StreamChunk> {
+        let data = line.trim().strip_prefix("data: ")?;
+        let event: serde_json::Value = serde_json::from_str(data).ok()?;
+
+        match event.get("type").and_then(|v| v.as_str())? {
+            "message_start" => {
+                let message = event.get("message")?;
+                Some(StreamChunk {
+                    id: message.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 0,
+                    model: message.get("model").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                    choices: vec![StreamChoice {
+                        index: 0,
+                        delta: StreamDelta {
+                            role: Some("assistant".to_string()),
+                            content: None,
+                            tool_calls: None,
+                        },
+                        finish_reason: None,
+                    }],
+                    usage: None,
+                })
+            }
+            "content_block_start" => {
+                let block = event.get("content_block")?;
+                if block.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+                    return None;
+                }
+
+                let index = event.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                let id = block.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                let name = block.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+                Some(tool_call_delta_chunk(
+                    index,
+                    Some(id),
+                    Some("function".to_string()),
+                    Some(name),
+                    None,
+                ))
+            }
+            "content_block_delta" => {
+                let delta = event.get("delta")?;
+
+                match delta.get("type").and_then(|v| v.as_str()) {
+                    Some("input_json_delta") => {
+                        let index = event.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                        let partial_json = delta.get("partial_json").and_then(|v| v.as_str())?.to_string();
+
+                        Some(tool_call_delta_chunk(index, None, None, None, Some(partial_json)))
+                    }
+                    _ => {
+                        let text = delta.get("text").and_then(|v| v.as_str())?.to_string();
+                        Some(StreamChunk {
+                            id: String::new(),
+                            object: "chat.completion.chunk".to_string(),
+                            created: 0,
+                            model: String::new(),
+                            choices: vec![StreamChoice {
+                                index: 0,
+                                delta: StreamDelta {
+                                    role: None,
+                                    content: Some(text),
+                                    tool_calls: None,
+                                },
+                                finish_reason: None,
+                            }],
+                            usage: None,
+                        })
+                    }
+                }
+            }
+            "message_delta" => {
+                let finish_reason = event
+                    .get("delta")
+                    .and_then(|d| d.get("stop_reason"))
+                    .and_then(|v| v.as_str())
+                    .map(map_stop_reason);
+                let usage = event.get("usage").map(|usage| {
+                    let output_tokens = usage.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                    Usage {
+                        prompt_tokens: 0,
+                        completion_tokens: output_tokens,
+                        total_tokens: output_tokens,
+                    }
+                });
+                Some(StreamChunk {
+                    id: String::new(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 0,
+                    model: String::new(),
+                    choices: vec![StreamChoice {
+                        index: 0,
+                        delta: StreamDelta::default(),
+                        finish_reason,
+                    }],
+                    usage,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Wrap a single `tool_calls` delta (from a `content_block_start` or
+/// `input_json_delta` event) in the minimal `StreamChunk` shape the
+/// OpenAI-style accumulator expects, matching how the plain-text delta
+/// branch above is built.
+fn tool_call_delta_chunk(
+    index: u32,
+    id: Option<String>,
+    call_type: Option<String>,
+    name: Option<String>,
+    arguments: Option<String>,
+) -> StreamChunk {
+    StreamChunk {
+        id: String::new(),
+        object: "chat.completion.chunk".to_string(),
+        created: 0,
+        model: String::new(),
+        choices: vec![StreamChoice {
+            index: 0,
+            delta: StreamDelta {
+                role: None,
+                content: None,
+                tool_calls: Some(vec![ToolCallDelta {
+                    index,
+                    id,
+                    call_type,
+                    function: Some(FunctionDelta { name, arguments }),
+                }]),
+            },
+            finish_reason: None,
+        }],
+        usage: None,
+    }
+}
+
+/// Map Anthropic's `stop_reason` to the OpenAI-style `finish_reason` callers expect
+fn map_stop_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "tool_use" => "tool_calls",
+        "max_tokens" => "length",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Remove and return the leading system message's text, if present, so it
+/// can be promoted to Anthropic's top-level `system` field.
+fn take_leading_system_prompt(messages: &mut Vec<Message>) -> Option<String> {
+    if messages.first().map(|m| m.role == "system") == Some(true) {
+        Some(messages.remove(0).content.to_string_content())
+    } else {
+        None
+    }
+}
+
+/// Map a `ToolChoice` to Anthropic's `tool_choice` shape: `"auto"`/`"required"`
+/// become `{"type": "auto"}`/`{"type": "any"}`, `"none"` becomes
+/// `{"type": "none"}`, and a forced function becomes `{"type": "tool", "name": ...}`.
+fn to_anthropic_tool_choice(tool_choice: &ToolChoice) -> serde_json::Value {
+    match tool_choice {
+        ToolChoice::Mode(mode) => match mode.as_str() {
+            "required" => json!({"type": "any"}),
+            "none" => json!({"type": "none"}),
+            _ => json!({"type": "auto"}),
+        },
+        ToolChoice::Function { function, .. } => json!({"type": "tool", "name": function.name}),
+    }
+}
+
+/// Convert a message into Anthropic's `{role, content: [blocks]}` shape
+fn to_anthropic_message(message: &Message) -> serde_json::Value {
+    let mut blocks = to_content_blocks(&message.content);
+
+    if let Some(tool_calls) = &message.tool_calls {
+        for call in tool_calls {
+            blocks.push(json!({
+                "type": "tool_use",
+                "id": call.id,
+                "name": call.function.name,
+                "input": serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                    .unwrap_or(json!({})),
+            }));
+        }
+    }
+
+    if message.role == "tool" {
+        return json!({
+            "role": "user",
+            "content": [{
+                "type": "tool_result",
+                "tool_use_id": message.tool_call_id.clone().unwrap_or_default(),
+                "content": message.content.to_string_content(),
+            }],
+        });
+    }
+
+    json!({
+        "role": if message.role == "assistant" { "assistant" } else { "user" },
+        "content": blocks,
+    })
+}
+
+/// Convert message content into Anthropic content blocks
+fn to_content_blocks(content: &MessageContent) -> Vec<serde_json::Value> {
+    match content {
+        MessageContent::Text(text) => vec![json!({"type": "text", "text": text})],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({"type": "text", "text": text}),
+                ContentPart::ImageUrl { image_url } => json!({
+                    "type": "image",
+                    "source": {"type": "url", "url": image_url.url},
+                }),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Message;
+
+    fn test_config(backend: &str) -> ProviderConfig {
+        ProviderConfig {
+            base_url: "https://api.anthropic.com".to_string(),
+            api_key_env: None,
+            api_keys_env: None,
+            api_base_env: None,
+            models: vec![],
+            param_mappings: Default::default(),
+            headers: Default::default(),
+            rate_limit: None,
+            special_handling: Default::default(),
+            encoding: None,
+            backend: Some(backend.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_url() {
+        let url = AnthropicBackend.build_url("https://api.anthropic.com", "claude-3-opus");
+        assert_eq!(url, "https://api.anthropic.com/v1/messages");
+    }
+
+    #[test]
+    fn test_build_body_lifts_system_message() {
+        let request = CompletionRequest::new(
+            "claude-3-opus".to_string(),
+            vec![
+                Message {
+                    role: "system".to_string(),
+                    content: MessageContent::Text("Be concise.".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: MessageContent::Text("Hi".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+            ],
+        );
+        let config = test_config("anthropic");
+
+        let body = AnthropicBackend.build_body(&request, &config);
+        assert_eq!(body["system"], json!("Be concise."));
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["max_tokens"], json!(DEFAULT_MAX_TOKENS));
+    }
+
+    #[test]
+    fn test_build_body_maps_tool_choice() {
+        use crate::api::ToolChoiceFunction;
+
+        let message = Message {
+            role: "user".to_string(),
+            content: MessageContent::Text("Hi".to_string()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let config = test_config("anthropic");
+
+        let mut request = CompletionRequest::new("claude-3-opus".to_string(), vec![message.clone()]);
+        request.tool_choice = Some(ToolChoice::Mode("required".to_string()));
+        assert_eq!(
+            AnthropicBackend.build_body(&request, &config)["tool_choice"],
+            json!({"type": "any"})
+        );
+
+        request.tool_choice = Some(ToolChoice::Mode("none".to_string()));
+        assert_eq!(
+            AnthropicBackend.build_body(&request, &config)["tool_choice"],
+            json!({"type": "none"})
+        );
+
+        request.tool_choice = Some(ToolChoice::Function {
+            r#type: "function".to_string(),
+            function: ToolChoiceFunction { name: "get_weather".to_string() },
+        });
+        assert_eq!(
+            AnthropicBackend.build_body(&request, &config)["tool_choice"],
+            json!({"type": "tool", "name": "get_weather"})
+        );
+    }
+
+    #[test]
+    fn test_parse_response_maps_stop_reason_and_usage() {
+        let raw = json!({
+            "id": "msg_123",
+            "model": "claude-3-opus",
+            "stop_reason": "end_turn",
+            "content": [{"type": "text", "text": "Hello!"}],
+            "usage": {"input_tokens": 10, "output_tokens": 5},
+        });
+
+        let response = AnthropicBackend.parse_response(raw).unwrap();
+        assert_eq!(response.content(), Some("Hello!".to_string()));
+        assert_eq!(response.choices[0].finish_reason, Some("stop".to_string()));
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+    }
+
+    #[test]
+    fn test_parse_stream_event_content_delta() {
+        let line = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#;
+        let chunk = AnthropicBackend.parse_stream_event(line).unwrap();
+        assert_eq!(chunk.choices[0].delta.content, Some("Hi".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stream_event_message_delta_maps_stop_reason() {
+        let line = r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":3}}"#;
+        let chunk = AnthropicBackend.parse_stream_event(line).unwrap();
+        assert_eq!(chunk.choices[0].finish_reason, Some("stop".to_string()));
+        assert_eq!(chunk.usage.unwrap().completion_tokens, 3);
+    }
+
+    #[test]
+    fn test_parse_stream_event_content_block_start_emits_tool_call_delta() {
+        let line = r#"data: {"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"get_weather","input":{}}}"#;
+        let chunk = AnthropicBackend.parse_stream_event(line).unwrap();
+        let tool_calls = chunk.choices[0].delta.tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls[0].index, 1);
+        assert_eq!(tool_calls[0].id, Some("toolu_1".to_string()));
+        assert_eq!(tool_calls[0].call_type, Some("function".to_string()));
+        assert_eq!(tool_calls[0].function.as_ref().unwrap().name, Some("get_weather".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stream_event_content_block_start_ignores_text_blocks() {
+        let line = r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#;
+        assert!(AnthropicBackend.parse_stream_event(line).is_none());
+    }
+
+    #[test]
+    fn test_parse_stream_event_input_json_delta_accumulates_into_tool_call() {
+        use crate::api::StreamAccumulator;
+
+        let start = r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"get_weather","input":{}}}"#;
+        let delta1 = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"city\": "}}"#;
+        let delta2 = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"Berlin\"}"}}"#;
+
+        let mut acc = StreamAccumulator::new();
+        for line in [start, delta1, delta2] {
+            let chunk = AnthropicBackend.parse_stream_event(line).unwrap();
+            acc.process_chunk(&chunk).unwrap();
+        }
+
+        let message = acc.into_message();
+        let tool_calls = message.tool_calls.unwrap();
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+        assert_eq!(tool_calls[0].function.arguments, r#"{"city": "Berlin"}"#);
+    }
+
+    #[test]
+    fn test_parse_stream_event_ignores_non_data_lines() {
+        assert!(AnthropicBackend.parse_stream_event("event: content_block_delta").is_none());
+        assert!(AnthropicBackend.parse_stream_event("").is_none());
+    }
+}
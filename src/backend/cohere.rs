@@ -0,0 +1,275 @@
+//! Cohere Chat API backend
+//!
+//! Cohere splits a conversation into a `preamble` (system prompt),
+//! `chat_history` (every turn but the last), and `message` (the latest
+//! user turn), rather than a flat `messages` array. Its streaming response
+//! is newline-delimited JSON objects tagged by `event_type`, not
+//! `data: `-prefixed SSE.
+
+use crate::api::{
+    Choice, CompletionRequest, CompletionResponse, Message, MessageContent, StreamChoice,
+    StreamChunk, StreamDelta, Usage,
+};
+use crate::backend::ProviderBackend;
+use crate::config::ProviderConfig;
+use crate::error::Result;
+use serde_json::json;
+
+/// Backend for Cohere's Chat API
+pub struct CohereBackend;
+
+impl ProviderBackend for CohereBackend {
+    fn build_url(&self, base_url: &str, _model: &str) -> String {
+        format!("{}/v1/chat", base_url.trim_end_matches('/'))
+    }
+
+    fn build_body(&self, request: &CompletionRequest, config: &ProviderConfig) -> serde_json::Value {
+        let mut messages = request.messages.clone();
+        let preamble = if messages.first().map(|m| m.role == "system") == Some(true) {
+            Some(messages.remove(0).content.to_string_content())
+        } else {
+            None
+        };
+
+        let last_user_message = messages
+            .pop()
+            .map(|m| m.content.to_string_content())
+            .unwrap_or_default();
+
+        let chat_history = messages
+            .iter()
+            .map(|message| {
+                json!({
+                    "role": cohere_role(&message.role),
+                    "message": message.content.to_string_content(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut body = json!({
+            "model": request.model,
+            "message": last_user_message,
+            "chat_history": chat_history,
+        });
+
+        let obj = body.as_object_mut().expect("body is always an object");
+        if let Some(preamble) = preamble {
+            obj.insert("preamble".to_string(), json!(preamble));
+        }
+        if let Some(temperature) = request.temperature {
+            obj.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            obj.insert("max_tokens".to_string(), json!(max_tokens));
+        }
+        if let Some(stream) = request.stream {
+            obj.insert("stream".to_string(), json!(stream));
+        }
+
+        config.apply_param_mappings(&mut body);
+        body
+    }
+
+    fn parse_response(&self, raw: serde_json::Value) -> Result<CompletionResponse> {
+        let text = raw.get("text").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let id = raw
+            .get("generation_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let usage = raw
+            .get("meta")
+            .and_then(|meta| meta.get("billed_units"))
+            .map(|billed| {
+                let prompt_tokens = billed.get("input_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                let completion_tokens = billed.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                Usage {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                }
+            });
+
+        Ok(CompletionResponse {
+            id,
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: raw.get("model").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content: MessageContent::Text(text),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                finish_reason: raw
+                    .get("finish_reason")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_lowercase()),
+            }],
+            usage,
+        })
+    }
+
+    fn parse_stream_event(&self, line: &str) -> Option<StreamChunk> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        let event: serde_json::Value = serde_json::from_str(line).ok()?;
+
+        match event.get("event_type").and_then(|v| v.as_str())? {
+            "text-generation" => {
+                let text = event.get("text").and_then(|v| v.as_str())?.to_string();
+                Some(StreamChunk {
+                    id: String::new(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 0,
+                    model: String::new(),
+                    choices: vec![StreamChoice {
+                        index: 0,
+                        delta: StreamDelta {
+                            role: None,
+                            content: Some(text),
+                            tool_calls: None,
+                        },
+                        finish_reason: None,
+                    }],
+                    usage: None,
+                })
+            }
+            "stream-end" => {
+                let finish_reason = event
+                    .get("finish_reason")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_lowercase());
+                Some(StreamChunk {
+                    id: String::new(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 0,
+                    model: String::new(),
+                    choices: vec![StreamChoice {
+                        index: 0,
+                        delta: StreamDelta::default(),
+                        finish_reason,
+                    }],
+                    usage: None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn supports_tools(&self) -> bool {
+        false
+    }
+}
+
+/// Map our role names to Cohere's `USER`/`CHATBOT` chat history roles
+fn cohere_role(role: &str) -> &'static str {
+    match role {
+        "assistant" => "CHATBOT",
+        _ => "USER",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Message;
+
+    fn test_config() -> ProviderConfig {
+        ProviderConfig {
+            base_url: "https://api.cohere.ai".to_string(),
+            api_key_env: None,
+            api_keys_env: None,
+            api_base_env: None,
+            models: vec![],
+            param_mappings: Default::default(),
+            headers: Default::default(),
+            rate_limit: None,
+            special_handling: Default::default(),
+            encoding: None,
+            backend: Some("cohere".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_url() {
+        let url = CohereBackend.build_url("https://api.cohere.ai", "command-r");
+        assert_eq!(url, "https://api.cohere.ai/v1/chat");
+    }
+
+    #[test]
+    fn test_build_body_splits_history_and_message() {
+        let request = CompletionRequest::new(
+            "command-r".to_string(),
+            vec![
+                Message {
+                    role: "system".to_string(),
+                    content: MessageContent::Text("Be helpful.".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: MessageContent::Text("Hi".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                Message {
+                    role: "assistant".to_string(),
+                    content: MessageContent::Text("Hello!".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: MessageContent::Text("How are you?".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+            ],
+        );
+        let config = test_config();
+
+        let body = CohereBackend.build_body(&request, &config);
+        assert_eq!(body["preamble"], json!("Be helpful."));
+        assert_eq!(body["message"], json!("How are you?"));
+        assert_eq!(body["chat_history"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_response_reads_billed_units() {
+        let raw = json!({
+            "text": "I'm doing well!",
+            "generation_id": "gen-1",
+            "meta": {"billed_units": {"input_tokens": 12, "output_tokens": 4}},
+        });
+
+        let response = CohereBackend.parse_response(raw).unwrap();
+        assert_eq!(response.content(), Some("I'm doing well!".to_string()));
+        assert_eq!(response.usage.unwrap().total_tokens, 16);
+    }
+
+    #[test]
+    fn test_parse_stream_event_text_generation() {
+        let line = r#"{"event_type":"text-generation","text":"Hi"}"#;
+        let chunk = CohereBackend.parse_stream_event(line).unwrap();
+        assert_eq!(chunk.choices[0].delta.content, Some("Hi".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stream_event_stream_end() {
+        let line = r#"{"event_type":"stream-end","finish_reason":"COMPLETE"}"#;
+        let chunk = CohereBackend.parse_stream_event(line).unwrap();
+        assert_eq!(chunk.choices[0].finish_reason, Some("complete".to_string()));
+    }
+}
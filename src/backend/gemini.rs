@@ -0,0 +1,251 @@
+//! Google Gemini generateContent API backend
+//!
+//! Gemini's wire format nests the model into the URL path rather than the
+//! body, calls messages `contents` with `parts` instead of `content`, uses
+//! `model`/`user` roles instead of `assistant`/`user`, and nests sampling
+//! parameters under a `generationConfig` object. Streaming is SSE with one
+//! full response object (not an incremental delta) per event.
+
+use crate::api::{
+    Choice, CompletionRequest, CompletionResponse, Message, MessageContent, StreamChoice,
+    StreamChunk, StreamDelta, Usage,
+};
+use crate::backend::ProviderBackend;
+use crate::config::ProviderConfig;
+use crate::error::Result;
+use serde_json::json;
+
+/// Backend for Google's Gemini `generateContent` API
+pub struct GeminiBackend;
+
+impl ProviderBackend for GeminiBackend {
+    fn build_url(&self, base_url: &str, model: &str) -> String {
+        format!(
+            "{}/v1beta/models/{}:generateContent",
+            base_url.trim_end_matches('/'),
+            model
+        )
+    }
+
+    fn build_body(&self, request: &CompletionRequest, config: &ProviderConfig) -> serde_json::Value {
+        let mut messages = request.messages.clone();
+        let system = take_leading_system_prompt(&mut messages);
+
+        let mut body = json!({
+            "contents": messages.iter().map(to_gemini_content).collect::<Vec<_>>(),
+        });
+
+        let obj = body.as_object_mut().expect("body is always an object");
+        if let Some(system) = system {
+            obj.insert(
+                "systemInstruction".to_string(),
+                json!({"parts": [{"text": system}]}),
+            );
+        }
+
+        let mut generation_config = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if let Some(top_p) = request.top_p {
+            generation_config.insert("topP".to_string(), json!(top_p));
+        }
+        if !generation_config.is_empty() {
+            obj.insert(
+                "generationConfig".to_string(),
+                serde_json::Value::Object(generation_config),
+            );
+        }
+
+        config.apply_param_mappings(&mut body);
+        body
+    }
+
+    fn parse_response(&self, raw: serde_json::Value) -> Result<CompletionResponse> {
+        let candidate = raw.get("candidates").and_then(|v| v.as_array()).and_then(|c| c.first());
+
+        let text = candidate
+            .and_then(|c| c.get("content"))
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array())
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|part| part.get("text").and_then(|v| v.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("")
+            })
+            .unwrap_or_default();
+
+        let finish_reason = candidate
+            .and_then(|c| c.get("finishReason"))
+            .and_then(|v| v.as_str())
+            .map(map_finish_reason);
+
+        let usage = raw.get("usageMetadata").map(|usage| {
+            let prompt_tokens = usage.get("promptTokenCount").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            let completion_tokens = usage.get("candidatesTokenCount").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: usage.get("totalTokenCount").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            }
+        });
+
+        Ok(CompletionResponse {
+            id: String::new(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: String::new(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content: MessageContent::Text(text),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                finish_reason,
+            }],
+            usage,
+        })
+    }
+
+    fn parse_stream_event(&self, line: &str) -> Option<StreamChunk> {
+        let data = line.trim().strip_prefix("data: ")?;
+        let event: serde_json::Value = serde_json::from_str(data).ok()?;
+        let response = self.parse_response(event).ok()?;
+        let choice = response.choices.into_iter().next()?;
+
+        Some(StreamChunk {
+            id: String::new(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: String::new(),
+            choices: vec![StreamChoice {
+                index: 0,
+                delta: StreamDelta {
+                    role: None,
+                    content: Some(choice.message.content.to_string_content()),
+                    tool_calls: None,
+                },
+                finish_reason: choice.finish_reason,
+            }],
+            usage: response.usage,
+        })
+    }
+
+    fn supports_tools(&self) -> bool {
+        false
+    }
+}
+
+/// Map Gemini's `finishReason` to the OpenAI-style `finish_reason` callers expect
+fn map_finish_reason(finish_reason: &str) -> String {
+    match finish_reason {
+        "STOP" => "stop",
+        "MAX_TOKENS" => "length",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Remove and return the leading system message's text, if present, so it
+/// can be promoted to Gemini's top-level `systemInstruction` field.
+fn take_leading_system_prompt(messages: &mut Vec<Message>) -> Option<String> {
+    if messages.first().map(|m| m.role == "system") == Some(true) {
+        Some(messages.remove(0).content.to_string_content())
+    } else {
+        None
+    }
+}
+
+/// Convert a message into Gemini's `{role, parts: [...]}` shape
+fn to_gemini_content(message: &Message) -> serde_json::Value {
+    let role = if message.role == "assistant" { "model" } else { "user" };
+    json!({
+        "role": role,
+        "parts": [{"text": message.content.to_string_content()}],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Message;
+
+    fn test_config() -> ProviderConfig {
+        ProviderConfig {
+            base_url: "https://generativelanguage.googleapis.com".to_string(),
+            api_key_env: None,
+            api_keys_env: None,
+            api_base_env: None,
+            models: vec![],
+            param_mappings: Default::default(),
+            headers: Default::default(),
+            rate_limit: None,
+            special_handling: Default::default(),
+            encoding: None,
+            backend: Some("gemini".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_url_embeds_model() {
+        let url = GeminiBackend.build_url("https://generativelanguage.googleapis.com", "gemini-1.5-pro");
+        assert_eq!(
+            url,
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-1.5-pro:generateContent"
+        );
+    }
+
+    #[test]
+    fn test_build_body_lifts_system_message() {
+        let request = CompletionRequest::new(
+            "gemini-1.5-pro".to_string(),
+            vec![
+                Message {
+                    role: "system".to_string(),
+                    content: MessageContent::Text("Be concise.".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: MessageContent::Text("Hi".to_string()),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+            ],
+        )
+        .with_max_tokens(100);
+        let config = test_config();
+
+        let body = GeminiBackend.build_body(&request, &config);
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], json!("Be concise."));
+        assert_eq!(body["contents"].as_array().unwrap().len(), 1);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], json!(100));
+    }
+
+    #[test]
+    fn test_parse_response_reads_candidate_and_usage() {
+        let raw = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Hello!"}], "role": "model"},
+                "finishReason": "STOP",
+            }],
+            "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 5, "totalTokenCount": 15},
+        });
+
+        let response = GeminiBackend.parse_response(raw).unwrap();
+        assert_eq!(response.content(), Some("Hello!".to_string()));
+        assert_eq!(response.choices[0].finish_reason, Some("stop".to_string()));
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+    }
+}
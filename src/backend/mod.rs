@@ -0,0 +1,58 @@
+//! Provider Backends
+//!
+//! The crate's `CompletionRequest`/`CompletionResponse`/`StreamChunk` types
+//! are OpenAI-shaped. A `ProviderBackend` translates them into and out of
+//! the wire format a specific provider actually expects -- request URL,
+//! request body, response shape, and streaming event format -- so callers
+//! build one request and let the configured backend handle the divergence.
+
+mod anthropic;
+mod cohere;
+mod gemini;
+mod openai;
+
+use crate::api::{CompletionRequest, CompletionResponse, StreamChunk};
+use crate::config::ProviderConfig;
+use crate::error::Result;
+
+pub use anthropic::AnthropicBackend;
+pub use cohere::CohereBackend;
+pub use gemini::GeminiBackend;
+pub use openai::OpenAiBackend;
+
+/// Translates between the crate's OpenAI-shaped types and a provider's wire format
+pub trait ProviderBackend: Send + Sync {
+    /// Build the request URL for a completion call against `base_url` for `model`
+    fn build_url(&self, base_url: &str, model: &str) -> String;
+
+    /// Build the request body to send to the provider. `request.model` and
+    /// `request.stream` are expected to already be set by the caller.
+    fn build_body(&self, request: &CompletionRequest, config: &ProviderConfig) -> serde_json::Value;
+
+    /// Parse the provider's raw JSON response into a `CompletionResponse`
+    fn parse_response(&self, raw: serde_json::Value) -> Result<CompletionResponse>;
+
+    /// Parse one line of a streaming response into a `StreamChunk`, if that
+    /// line carries one (event-type/id lines, keep-alives, and malformed
+    /// payloads all yield `None`).
+    fn parse_stream_event(&self, line: &str) -> Option<StreamChunk>;
+
+    /// Whether this backend translates `CompletionRequest::tools` into its
+    /// wire format. Providers that don't advertise tool support should
+    /// reject tool-calling requests instead of silently dropping the
+    /// tool schemas.
+    fn supports_tools(&self) -> bool {
+        true
+    }
+}
+
+/// Resolve the backend for a provider from its `backend` config tag,
+/// falling back to OpenAI-compatible handling if unset or unrecognized.
+pub fn backend_for(config: &ProviderConfig) -> Box<dyn ProviderBackend> {
+    match config.backend.as_deref() {
+        Some("anthropic") => Box::new(AnthropicBackend),
+        Some("cohere") => Box::new(CohereBackend),
+        Some("gemini") => Box::new(GeminiBackend),
+        _ => Box::new(OpenAiBackend),
+    }
+}
@@ -0,0 +1,43 @@
+//! OpenAI-compatible backend
+//!
+//! The default backend for providers that speak the OpenAI chat completion
+//! shape natively. Applies `SpecialHandling` quirks and `param_mappings`
+//! directly to the request, and passes the response/stream through
+//! unchanged.
+
+use crate::api::{self, CompletionRequest, CompletionResponse, StreamChunk};
+use crate::backend::ProviderBackend;
+use crate::config::ProviderConfig;
+use crate::error::Result;
+
+/// Backend for OpenAI-compatible providers (the default)
+pub struct OpenAiBackend;
+
+impl ProviderBackend for OpenAiBackend {
+    fn build_url(&self, base_url: &str, _model: &str) -> String {
+        format!("{}/chat/completions", base_url.trim_end_matches('/'))
+    }
+
+    fn build_body(&self, request: &CompletionRequest, config: &ProviderConfig) -> serde_json::Value {
+        let mut request = request.clone();
+
+        if config.special_handling.convert_content_list_to_string {
+            request.convert_content_to_strings();
+        }
+        if config.special_handling.add_text_to_tool_calls {
+            request.add_text_to_tool_calls();
+        }
+
+        let mut body = serde_json::to_value(&request).unwrap_or(serde_json::Value::Null);
+        config.apply_param_mappings(&mut body);
+        body
+    }
+
+    fn parse_response(&self, raw: serde_json::Value) -> Result<CompletionResponse> {
+        Ok(serde_json::from_value(raw)?)
+    }
+
+    fn parse_stream_event(&self, line: &str) -> Option<StreamChunk> {
+        api::parse_sse_line(line).ok().flatten()
+    }
+}
@@ -2,34 +2,96 @@
 //!
 //! Async HTTP client with retry, backoff, and rate limit handling.
 
+use crate::api::{parse_sse_id, parse_sse_retry, StreamChunk};
+use crate::backend::ProviderBackend;
 use crate::client::rate_limiter::RateLimitTracker;
+use crate::config::{ClientConfig, ProxyConfig};
 use crate::error::{LlmaoError, Result};
 use backoff::ExponentialBackoff;
+#[cfg(not(feature = "blocking"))]
 use futures::Stream;
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use reqwest::{Client, StatusCode};
+// `maybe_async::maybe_async` compiles this module's shared methods as
+// written (async, `.await`ing `reqwest::Client`) by default, and strips the
+// `async`/`.await` down to sync, blocking `reqwest::blocking::Client` calls
+// when this crate's `blocking` feature enables `maybe-async`'s `is_sync`.
+use maybe_async::maybe_async;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+#[cfg(not(feature = "blocking"))]
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
-/// HTTP client with retry and rate limit handling
+/// Swaps between `reqwest`'s async and blocking clients depending on the
+/// `blocking` feature, so [`HttpClient`]'s fields and builder stay a single
+/// source shared by both forms.
+#[cfg(not(feature = "blocking"))]
+type InnerClient = reqwest::Client;
+#[cfg(feature = "blocking")]
+type InnerClient = reqwest::blocking::Client;
+
+/// HTTP client with retry and rate limit handling. Async by default; built
+/// with `--features blocking` it runs on `std::thread::sleep` and
+/// `reqwest::blocking` instead of requiring a Tokio runtime, for CLI/script
+/// callers that only ever make one request at a time.
+#[derive(Clone)]
 pub struct HttpClient {
     /// Inner reqwest client
-    client: Client,
+    client: InnerClient,
 
     /// Rate limit tracker
     rate_limiter: Arc<RateLimitTracker>,
 }
 
 impl HttpClient {
-    /// Create a new HTTP client
+    /// Create a new HTTP client with no proxy configured
     pub fn new() -> Result<Self> {
-        let client = Client::builder()
+        Self::with_proxy(None)
+    }
+
+    /// Create a new HTTP client, routing requests through `proxy` if given.
+    /// An HTTP or SOCKS5 proxy URL is accepted; if `proxy.url` is unset the
+    /// `HTTP_PROXY`/`HTTPS_PROXY`/`ALL_PROXY` environment variables are
+    /// consulted instead, and hosts in `proxy.no_proxy` (or `NO_PROXY`) bypass it.
+    pub fn with_proxy(proxy: Option<&ProxyConfig>) -> Result<Self> {
+        Self::with_config(proxy, None)
+    }
+
+    /// Create a new HTTP client with both outbound proxying and transport
+    /// settings (e.g. response compression) configured.
+    pub fn with_config(proxy: Option<&ProxyConfig>, client_config: Option<&ClientConfig>) -> Result<Self> {
+        let connect_timeout = proxy
+            .and_then(|p| p.connect_timeout_secs)
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(10));
+
+        let compression = client_config.cloned().unwrap_or_default();
+
+        let mut builder = InnerClient::builder()
             .timeout(Duration::from_secs(300)) // 5 minute timeout for long completions
-            .connect_timeout(Duration::from_secs(10))
+            .connect_timeout(connect_timeout)
             .pool_max_idle_per_host(10)
+            .gzip(compression.wants("gzip"))
+            .deflate(compression.wants("deflate"))
+            .zstd(compression.wants("zstd"));
+
+        if let Some(proxy_config) = proxy {
+            if let Some(url) = proxy_config.resolve_url() {
+                let mut route = reqwest::Proxy::all(&url)
+                    .map_err(|e| LlmaoError::Config(format!("Invalid proxy URL '{}': {}", url, e)))?;
+
+                let no_proxy = proxy_config.resolve_no_proxy();
+                if !no_proxy.is_empty() {
+                    route = route.no_proxy(reqwest::NoProxy::from_string(&no_proxy.join(",")));
+                }
+
+                builder = builder.proxy(route);
+            }
+        }
+
+        let client = builder
             .build()
             .map_err(|e| LlmaoError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
@@ -44,7 +106,11 @@ impl HttpClient {
         &self.rate_limiter
     }
 
-    /// Make a POST request with retry logic
+    /// Make a POST request with retry logic. `estimated_tokens`, if given,
+    /// is forwarded to the rate limiter's proactive gate so it can also
+    /// throttle on the token budget, not just the request-count window.
+    #[maybe_async]
+    #[allow(clippy::too_many_arguments)]
     pub async fn post_with_retry<T, R>(
         &self,
         url: &str,
@@ -53,6 +119,7 @@ impl HttpClient {
         extra_headers: Option<&HeaderMap>,
         provider: &str,
         max_retries: u32,
+        estimated_tokens: Option<u32>,
     ) -> Result<R>
     where
         T: Serialize,
@@ -86,10 +153,14 @@ impl HttpClient {
         let mut retries = 0;
 
         loop {
-            // Check if we should wait due to rate limits
-            if let Some(wait) = self.rate_limiter.should_wait(provider) {
-                tokio::time::sleep(wait).await;
-            }
+            // Block here, before the request ever leaves the client, rather
+            // than only reacting after a 429 comes back. The gate is released
+            // as soon as this returns, so it only serializes the wait/reserve
+            // decision, not the outbound round-trip below.
+            #[cfg(not(feature = "blocking"))]
+            self.rate_limiter.acquire(provider, api_key, estimated_tokens).await;
+            #[cfg(feature = "blocking")]
+            self.rate_limiter.acquire_blocking(provider, api_key, estimated_tokens);
 
             let response = self
                 .client
@@ -102,16 +173,14 @@ impl HttpClient {
             match response {
                 Ok(resp) => {
                     let status = resp.status();
+                    let headers = resp.headers().clone();
 
                     // Update rate limit info from headers
-                    self.rate_limiter.update_from_response(
-                        provider,
-                        resp.headers(),
-                        None,
-                        None,
-                    );
+                    self.rate_limiter
+                        .update_from_response(provider, api_key, &headers, None, None);
 
                     if status.is_success() {
+                        self.rate_limiter.record_success(provider, api_key);
                         let body = resp.text().await?;
                         return serde_json::from_str(&body).map_err(|e| {
                             LlmaoError::Response(format!(
@@ -126,18 +195,23 @@ impl HttpClient {
 
                     // Handle rate limit
                     if RateLimitTracker::is_rate_limit_error(status.as_u16(), &response_body) {
-                        // Parse headers from a new request since we consumed the response
                         retries += 1;
+                        let wait = self
+                            .rate_limiter
+                            .update_from_rate_limit_error(provider, api_key, &headers, None);
+
                         if retries > max_retries {
                             return Err(LlmaoError::RateLimited {
                                 provider: provider.to_string(),
-                                retry_after: None,
+                                retry_after: Some(wait.as_secs()),
+                                retry_info: self.rate_limiter.retry_info_for(provider, api_key),
                             });
                         }
 
-                        // Wait with exponential backoff
-                        let wait = backoff.initial_interval * 2u32.pow(retries);
+                        #[cfg(not(feature = "blocking"))]
                         tokio::time::sleep(wait).await;
+                        #[cfg(feature = "blocking")]
+                        std::thread::sleep(wait);
                         continue;
                     }
 
@@ -164,7 +238,10 @@ impl HttpClient {
                     // Retry on connection errors
                     if e.is_connect() || e.is_timeout() {
                         let wait = backoff.initial_interval * 2u32.pow(retries);
+                        #[cfg(not(feature = "blocking"))]
                         tokio::time::sleep(wait).await;
+                        #[cfg(feature = "blocking")]
+                        std::thread::sleep(wait);
                         continue;
                     }
 
@@ -174,18 +251,9 @@ impl HttpClient {
         }
     }
 
-    /// Make a streaming POST request
-    pub async fn post_stream(
-        &self,
-        url: &str,
-        body: &impl Serialize,
-        api_key: &str,
-        extra_headers: Option<&HeaderMap>,
-        provider: &str,
-    ) -> Result<Pin<Box<dyn Stream<Item = Result<bytes::Bytes>> + Send>>> {
-        use async_stream::stream;
-        use futures::StreamExt;
-
+    /// Build the headers shared by both the async and blocking forms of
+    /// `post_stream`.
+    fn build_stream_headers(api_key: &str, extra_headers: Option<&HeaderMap>) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(
@@ -194,17 +262,39 @@ impl HttpClient {
                 .map_err(|e| LlmaoError::Config(format!("Invalid API key format: {}", e)))?,
         );
 
-        // Add extra headers
         if let Some(extra) = extra_headers {
             for (key, value) in extra {
                 headers.insert(key.clone(), value.clone());
             }
         }
 
-        // Check rate limits
-        if let Some(wait) = self.rate_limiter.should_wait(provider) {
-            tokio::time::sleep(wait).await;
-        }
+        Ok(headers)
+    }
+
+    /// Make a streaming POST request. `estimated_tokens`, if given, is
+    /// forwarded to the rate limiter's proactive gate the same way
+    /// [`Self::post_with_retry`] does.
+    #[cfg(not(feature = "blocking"))]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn post_stream(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        api_key: &str,
+        extra_headers: Option<&HeaderMap>,
+        provider: &str,
+        estimated_tokens: Option<u32>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<bytes::Bytes>> + Send>>> {
+        use async_stream::stream;
+        use futures::StreamExt;
+
+        let headers = Self::build_stream_headers(api_key, extra_headers)?;
+
+        // Block here, before the request ever leaves the client, rather
+        // than only reacting after a 429 comes back. The gate is released
+        // as soon as this returns, so it only serializes the wait/reserve
+        // decision, not the outbound round-trip below.
+        self.rate_limiter.acquire(provider, api_key, estimated_tokens).await;
 
         let response = self
             .client
@@ -215,18 +305,26 @@ impl HttpClient {
             .await?;
 
         let status = response.status();
+        let resp_headers = response.headers().clone();
 
         // Update rate limit info
         self.rate_limiter
-            .update_from_response(provider, response.headers(), None, None);
+            .update_from_response(provider, api_key, &resp_headers, None, None);
 
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
 
             if RateLimitTracker::is_rate_limit_error(status.as_u16(), &body) {
+                let wait = self.rate_limiter.update_from_rate_limit_error(
+                    provider,
+                    api_key,
+                    &resp_headers,
+                    None,
+                );
                 return Err(LlmaoError::RateLimited {
                     provider: provider.to_string(),
-                    retry_after: None,
+                    retry_after: Some(wait.as_secs()),
+                    retry_info: self.rate_limiter.retry_info_for(provider, api_key),
                 });
             }
 
@@ -246,6 +344,165 @@ impl HttpClient {
 
         Ok(Box::pin(s))
     }
+
+    /// Blocking counterpart of `post_stream`: same request and rate-limit
+    /// handling, but reads the response body synchronously on the calling
+    /// thread each time the iterator is advanced, handing back a plain `Iterator` instead of a `Stream` so callers without a
+    /// Tokio runtime can still read an SSE body incrementally.
+    #[cfg(feature = "blocking")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn post_stream(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+        api_key: &str,
+        extra_headers: Option<&HeaderMap>,
+        provider: &str,
+        estimated_tokens: Option<u32>,
+    ) -> Result<impl Iterator<Item = Result<bytes::Bytes>>> {
+        let headers = Self::build_stream_headers(api_key, extra_headers)?;
+
+        // Block here, before the request ever leaves the client, rather
+        // than only reacting after a 429 comes back.
+        self.rate_limiter.acquire_blocking(provider, api_key, estimated_tokens);
+
+        let response = self.client.post(url).headers(headers).json(body).send()?;
+
+        let status = response.status();
+        let resp_headers = response.headers().clone();
+
+        // Update rate limit info
+        self.rate_limiter
+            .update_from_response(provider, api_key, &resp_headers, None, None);
+
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+
+            if RateLimitTracker::is_rate_limit_error(status.as_u16(), &body) {
+                let wait = self.rate_limiter.update_from_rate_limit_error(
+                    provider,
+                    api_key,
+                    &resp_headers,
+                    None,
+                );
+                return Err(LlmaoError::RateLimited {
+                    provider: provider.to_string(),
+                    retry_after: Some(wait.as_secs()),
+                    retry_info: self.rate_limiter.retry_info_for(provider, api_key),
+                });
+            }
+
+            return Err(LlmaoError::Request(format!(
+                "Streaming request failed with status {}: {}",
+                status, body
+            )));
+        }
+
+        Ok(BlockingByteIterator { response })
+    }
+
+    /// Like [`Self::post_stream`], but transparently reconnects on a
+    /// mid-stream transport error instead of ending the `Stream` with one.
+    /// Tracks the last SSE `id:` seen and the server's `retry:` interval
+    /// across reconnects, replaying the request with a `Last-Event-ID`
+    /// header so the caller sees one continuous stream rather than having
+    /// to detect drops and restart it themselves. A single
+    /// [`crate::api::StreamAccumulator`] is kept alive for the whole
+    /// duration so reconnects can't fork the accumulated message in two.
+    /// `backend` decodes each raw SSE line into a [`StreamChunk`] using that
+    /// provider's wire format, same as [`crate::backend::ProviderBackend::parse_stream_event`]
+    /// does for the non-resilient path. Not available under the `blocking`
+    /// feature: reconnect backoff here relies on an async sleep so other
+    /// work on the runtime isn't blocked while waiting to retry.
+    #[cfg(not(feature = "blocking"))]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn post_stream_resilient<T>(
+        &self,
+        url: &str,
+        body: &T,
+        api_key: &str,
+        extra_headers: Option<&HeaderMap>,
+        provider: &str,
+        backend: Box<dyn ProviderBackend>,
+        estimated_tokens: Option<u32>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>>
+    where
+        T: Serialize + Clone + Send + Sync + 'static,
+    {
+        use async_stream::stream;
+        use futures::StreamExt;
+
+        let client = self.clone();
+        let url = url.to_string();
+        let body = body.clone();
+        let api_key = api_key.to_string();
+        let provider = provider.to_string();
+        let mut headers = extra_headers.cloned().unwrap_or_default();
+
+        let s = stream! {
+            let mut last_event_id: Option<String> = None;
+            let mut retry_interval = Duration::from_secs(1);
+            let mut accumulator = crate::api::StreamAccumulator::new();
+
+            loop {
+                if let Some(id) = &last_event_id {
+                    if let Ok(value) = HeaderValue::from_str(id) {
+                        headers.insert(HeaderName::from_static("last-event-id"), value);
+                    }
+                }
+
+                let mut byte_stream = match client
+                    .post_stream(&url, &body, &api_key, Some(&headers), &provider, estimated_tokens)
+                    .await
+                {
+                    Ok(s) => s,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                };
+
+                let mut buffer = String::new();
+                let mut transport_error = false;
+
+                while let Some(result) = byte_stream.next().await {
+                    let bytes = match result {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            transport_error = true;
+                            break;
+                        }
+                    };
+                    buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                    while let Some(newline_pos) = buffer.find('\n') {
+                        let line = buffer[..newline_pos].to_string();
+                        buffer = buffer[newline_pos + 1..].to_string();
+
+                        if let Some(id) = parse_sse_id(&line) {
+                            last_event_id = Some(id);
+                        }
+                        if let Some(retry) = parse_sse_retry(&line) {
+                            retry_interval = retry;
+                        }
+
+                        if let Some(chunk) = backend.parse_stream_event(&line) {
+                            let _ = accumulator.process_chunk(&chunk);
+                            yield Ok(chunk);
+                        }
+                    }
+                }
+
+                if !transport_error {
+                    return;
+                }
+
+                tokio::time::sleep(retry_interval).await;
+            }
+        };
+
+        Ok(Box::pin(s))
+    }
 }
 
 impl Default for HttpClient {
@@ -254,6 +511,31 @@ impl Default for HttpClient {
     }
 }
 
+/// Adapts a `reqwest::blocking::Response`'s `Read` impl into the same
+/// `Iterator<Item = Result<bytes::Bytes>>` shape `post_stream` yields for
+/// the async client, reading incrementally so SSE lines become available
+/// as they arrive rather than after the whole body downloads.
+#[cfg(feature = "blocking")]
+struct BlockingByteIterator {
+    response: reqwest::blocking::Response,
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for BlockingByteIterator {
+    type Item = Result<bytes::Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use std::io::Read;
+
+        let mut buf = [0u8; 8192];
+        match self.response.read(&mut buf) {
+            Ok(0) => None,
+            Ok(n) => Some(Ok(bytes::Bytes::copy_from_slice(&buf[..n]))),
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,5 +545,137 @@ mod tests {
         let client = HttpClient::new();
         assert!(client.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_with_proxy_accepts_http_and_socks5() {
+        let http = ProxyConfig {
+            url: Some("http://127.0.0.1:8080".to_string()),
+            no_proxy: vec![],
+            connect_timeout_secs: None,
+        };
+        assert!(HttpClient::with_proxy(Some(&http)).is_ok());
+
+        let socks = ProxyConfig {
+            url: Some("socks5://127.0.0.1:1080".to_string()),
+            no_proxy: vec!["internal.example.com".to_string()],
+            connect_timeout_secs: Some(5),
+        };
+        assert!(HttpClient::with_proxy(Some(&socks)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_with_proxy_rejects_invalid_url() {
+        let bad = ProxyConfig {
+            url: Some("not a url".to_string()),
+            no_proxy: vec![],
+            connect_timeout_secs: None,
+        };
+        assert!(HttpClient::with_proxy(Some(&bad)).is_err());
+    }
+
+    /// Read a raw HTTP request off `socket` up through its headers and body,
+    /// returning the header block as text so the caller can assert on it
+    /// (e.g. the replayed `Last-Event-ID`).
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            if buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                break;
+            }
+        }
+        let text = String::from_utf8_lossy(&buf).to_string();
+
+        let content_length: usize = text
+            .lines()
+            .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let already_read = buf.len() - (text.find("\r\n\r\n").unwrap() + 4);
+        let mut remaining = content_length.saturating_sub(already_read);
+        while remaining > 0 {
+            let n = socket.read(&mut chunk).await.unwrap();
+            remaining = remaining.saturating_sub(n);
+        }
+
+        text
+    }
+
+    #[tokio::test]
+    async fn test_post_stream_resilient_reconnects_with_last_event_id() {
+        use crate::backend::OpenAiBackend;
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = format!("http://{}/v1/chat/completions", addr);
+
+        let server = tokio::spawn(async move {
+            // First connection: send one chunk, carrying an `id:` field,
+            // then drop the connection mid-body (no terminating `0\r\n\r\n`)
+            // to simulate a transport error.
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request(&mut socket).await;
+
+            let body1 = "id: evt-1\ndata: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":0,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hi\"}}]}\n\n";
+            socket
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                        body1.len(),
+                        body1
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            drop(socket);
+
+            // Second connection: the reconnect. It should replay with
+            // `Last-Event-ID: evt-1` so the caller sees one continuous stream.
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let headers = read_request(&mut socket).await;
+            assert!(
+                headers.to_ascii_lowercase().contains("last-event-id: evt-1"),
+                "reconnect should replay Last-Event-ID, got headers: {}",
+                headers
+            );
+
+            let body2 = "data: {\"id\":\"c2\",\"object\":\"chat.completion.chunk\",\"created\":0,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" there\"},\"finish_reason\":\"stop\"}]}\n\n";
+            socket
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                        body2.len(),
+                        body2
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+        });
+
+        let client = HttpClient::new().unwrap();
+        let body = serde_json::json!({"model": "m", "messages": []});
+        let mut stream = client
+            .post_stream_resilient(&url, &body, "test-key", None, "openai", Box::new(OpenAiBackend), None)
+            .await
+            .unwrap();
+
+        let mut contents = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            contents.push(chunk.choices[0].delta.content.clone().unwrap_or_default());
+        }
+
+        server.await.unwrap();
+        assert_eq!(contents, vec!["Hi".to_string(), " there".to_string()]);
+    }
 }
 
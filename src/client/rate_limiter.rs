@@ -2,150 +2,356 @@
 //!
 //! Tracks rate limits from API responses and manages wait times.
 
+use crate::error::{RetryInfo, RetrySource};
 use parking_lot::RwLock;
+use rand::Rng;
 use reqwest::header::HeaderMap;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 
-/// Tracks rate limit status for providers
+/// Starting point for decorrelated-jitter backoff, and the floor of every computed sleep
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound on any computed backoff, regardless of failure streak length
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Rate limit info is tracked per `(provider, key_id)` rather than per
+/// provider alone, so a 429 on one key doesn't pessimistically stall every
+/// other key in the pool.
+type TrackerKey = (String, String);
+
+/// Tracks rate limit status for providers, broken down by which API key was used
 #[derive(Debug, Default)]
 pub struct RateLimitTracker {
-    /// Per-provider rate limit info
-    providers: RwLock<HashMap<String, ProviderRateLimit>>,
+    /// Rate limit info keyed by `(provider, key_id)`
+    providers: RwLock<HashMap<TrackerKey, ProviderRateLimit>>,
+
+    /// Per-`(provider, key_id)` FIFO gate, so callers waiting on the same
+    /// key queue up in order rather than racing to recheck the window at
+    /// once. Scoped to the key, not the provider, so concurrent callers
+    /// using different keys in the same pool never block each other.
+    gates: RwLock<HashMap<TrackerKey, Arc<Semaphore>>>,
 }
 
-/// Rate limit info for a single provider
+/// Which rate-limit window a bucket tracks. Providers commonly expose
+/// several simultaneous windows for the same key (a per-minute request
+/// count, a per-minute token budget, a per-day request cap, ...), each
+/// counting down independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitType {
+    RequestsPerMinute,
+    TokensPerMinute,
+    RequestsPerDay,
+}
+
+/// State tracked for a single rate-limit window
 #[derive(Debug, Clone, Default)]
-pub struct ProviderRateLimit {
-    /// Remaining requests in current window
-    pub requests_remaining: Option<u32>,
+pub struct BucketState {
+    /// Total quota for the window, as last advertised by the provider
+    pub limit: Option<u64>,
 
-    /// Remaining tokens in current window
-    pub tokens_remaining: Option<u32>,
+    /// Remaining quota in the current window
+    pub remaining: Option<u32>,
 
-    /// When the rate limit resets
+    /// When this window resets
     pub reset_at: Option<Instant>,
 
-    /// Last known retry-after duration
+    /// Last known retry-after duration for this window
     pub retry_after: Option<Duration>,
 }
 
+/// Rate limit info for a single (provider, API key) pair, split by window
+#[derive(Debug, Clone, Default)]
+pub struct ProviderRateLimit {
+    /// State for each tracked window, keyed by which limit it represents
+    pub buckets: HashMap<LimitType, BucketState>,
+
+    /// Consecutive rate-limit failures observed for this key
+    pub failure_count: u32,
+
+    /// Sleep duration computed for the last backoff, seeding the next one
+    pub prev_sleep: Duration,
+}
+
+/// Compute a decorrelated-jitter backoff: `sleep = random(base, prev_sleep * 3)`,
+/// capped at `MAX_BACKOFF`. See https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/
+fn decorrelated_jitter(prev_sleep: Duration) -> Duration {
+    let prev = if prev_sleep.is_zero() { BASE_BACKOFF } else { prev_sleep };
+    let upper = (prev.saturating_mul(3)).min(MAX_BACKOFF).max(BASE_BACKOFF);
+
+    let low_ms = BASE_BACKOFF.as_millis() as u64;
+    let high_ms = upper.as_millis() as u64;
+    let sleep_ms = if high_ms <= low_ms {
+        low_ms
+    } else {
+        rand::thread_rng().gen_range(low_ms..=high_ms)
+    };
+
+    Duration::from_millis(sleep_ms).min(MAX_BACKOFF)
+}
+
 impl RateLimitTracker {
     /// Create a new rate limit tracker
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Update rate limit info from response headers
+    /// Update rate limit info from response headers. Populates every
+    /// bucket whose headers are present in this response; a provider that
+    /// only sends token headers leaves the request bucket untouched, and
+    /// vice versa.
     pub fn update_from_response(
         &self,
         provider: &str,
+        key_id: &str,
         headers: &HeaderMap,
         remaining_header: Option<&str>,
         reset_header: Option<&str>,
     ) {
         let mut providers = self.providers.write();
-        let info = providers.entry(provider.to_string()).or_default();
-
-        // Parse remaining requests header
-        let remaining_key = remaining_header.unwrap_or("x-ratelimit-remaining-requests");
-        if let Some(value) = headers.get(remaining_key) {
-            if let Ok(s) = value.to_str() {
-                if let Ok(n) = s.parse::<u32>() {
-                    info.requests_remaining = Some(n);
-                }
-            }
+        let info = providers
+            .entry((provider.to_string(), key_id.to_string()))
+            .or_default();
+
+        update_bucket_from_headers(
+            info.buckets.entry(LimitType::RequestsPerMinute).or_default(),
+            headers,
+            "x-ratelimit-limit-requests",
+            remaining_header.unwrap_or("x-ratelimit-remaining-requests"),
+            reset_header.unwrap_or("x-ratelimit-reset-requests"),
+        );
+
+        update_bucket_from_headers(
+            info.buckets.entry(LimitType::TokensPerMinute).or_default(),
+            headers,
+            "x-ratelimit-limit-tokens",
+            "x-ratelimit-remaining-tokens",
+            "x-ratelimit-reset-tokens",
+        );
+
+        update_bucket_from_headers(
+            info.buckets.entry(LimitType::RequestsPerDay).or_default(),
+            headers,
+            "x-ratelimit-limit-requests-day",
+            "x-ratelimit-remaining-requests-day",
+            "x-ratelimit-reset-requests-day",
+        );
+    }
+
+    /// Decrement locally tracked `remaining` counters immediately before a
+    /// request goes out, rather than waiting for the next response's headers
+    /// to refresh them. Without this, every caller queued behind [`Self::acquire`]
+    /// sees the same pre-request `remaining` count and they can collectively
+    /// overshoot the window before any of their responses come back.
+    fn reserve(&self, provider: &str, key_id: &str, estimated_tokens: Option<u32>) {
+        let mut providers = self.providers.write();
+        let Some(info) = providers.get_mut(&(provider.to_string(), key_id.to_string())) else {
+            return;
+        };
+
+        if let Some(bucket) = info.buckets.get_mut(&LimitType::RequestsPerMinute) {
+            bucket.remaining = bucket.remaining.map(|n| n.saturating_sub(1));
         }
 
-        // Parse reset header
-        let reset_key = reset_header.unwrap_or("x-ratelimit-reset-requests");
-        if let Some(value) = headers.get(reset_key) {
-            if let Ok(s) = value.to_str() {
-                // Try parsing as seconds
-                if let Ok(secs) = s.parse::<u64>() {
-                    info.reset_at = Some(Instant::now() + Duration::from_secs(secs));
-                }
-                // Try parsing as duration string (e.g., "1m30s")
-                else if let Some(duration) = parse_duration_string(s) {
-                    info.reset_at = Some(Instant::now() + duration);
-                }
+        if let Some(tokens) = estimated_tokens {
+            if let Some(bucket) = info.buckets.get_mut(&LimitType::TokensPerMinute) {
+                bucket.remaining = bucket.remaining.map(|n| n.saturating_sub(tokens));
             }
         }
     }
 
-    /// Update from a rate limit error response
+    /// Update from a rate limit error response. Computes a decorrelated-jitter
+    /// backoff seeded from the previous sleep, using any `retry-after` header
+    /// as a floor on that backoff rather than replacing it outright.
     pub fn update_from_rate_limit_error(
         &self,
         provider: &str,
+        key_id: &str,
         headers: &HeaderMap,
         retry_after_header: Option<&str>,
     ) -> Duration {
         let mut providers = self.providers.write();
-        let info = providers.entry(provider.to_string()).or_default();
+        let info = providers
+            .entry((provider.to_string(), key_id.to_string()))
+            .or_default();
+
+        info.failure_count += 1;
 
-        // Parse retry-after header
+        // Parse retry-after header (either a plain second count or an HTTP-date)
         let retry_key = retry_after_header.unwrap_or("retry-after");
-        let retry_duration = if let Some(value) = headers.get(retry_key) {
-            if let Ok(s) = value.to_str() {
-                // Try parsing as seconds
-                if let Ok(secs) = s.parse::<u64>() {
-                    Some(Duration::from_secs(secs))
-                }
-                // Try parsing as duration string
-                else {
-                    parse_duration_string(s)
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+        let retry_duration = headers
+            .get(retry_key)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+
+        let backoff = decorrelated_jitter(info.prev_sleep);
+        let duration = match retry_duration {
+            Some(retry) => backoff.max(retry),
+            None => backoff,
         };
 
-        // Default to 60 seconds if no header
-        let duration = retry_duration.unwrap_or(Duration::from_secs(60));
-        info.retry_after = Some(duration);
-        info.reset_at = Some(Instant::now() + duration);
+        info.prev_sleep = duration;
+
+        // A bare 429 with no bucket-specific headers is assumed to be the
+        // request window, since that's what trips on a generic rate limit
+        let bucket = info.buckets.entry(LimitType::RequestsPerMinute).or_default();
+        bucket.retry_after = retry_duration;
+        bucket.reset_at = Some(Instant::now() + duration);
 
         duration
     }
 
-    /// Check if we should wait before making a request
-    pub fn should_wait(&self, provider: &str) -> Option<Duration> {
+    /// Build retry metadata describing why `key_id` is currently rate
+    /// limited, to attach to a `RateLimited` error. Returns `None` if no
+    /// rate-limit state is tracked for this key, i.e. neither
+    /// [`Self::update_from_response`] nor [`Self::update_from_rate_limit_error`]
+    /// has been called for it.
+    pub fn retry_info_for(&self, provider: &str, key_id: &str) -> Option<RetryInfo> {
         let providers = self.providers.read();
+        let info = providers.get(&(provider.to_string(), key_id.to_string()))?;
+        let bucket = info.buckets.get(&LimitType::RequestsPerMinute)?;
+
+        // The header only floors the backoff, so it is the source only when
+        // it was at least as long as the delay actually applied.
+        let source = match bucket.retry_after {
+            Some(retry) if retry >= info.prev_sleep => RetrySource::Header,
+            _ => RetrySource::Backoff,
+        };
 
-        if let Some(info) = providers.get(provider) {
-            // Check if we're at zero remaining requests
-            if info.requests_remaining == Some(0) {
-                if let Some(reset_at) = info.reset_at {
-                    let now = Instant::now();
-                    if now < reset_at {
-                        return Some(reset_at - now);
-                    }
-                }
-            }
+        Some(RetryInfo {
+            bucket: LimitType::RequestsPerMinute,
+            source,
+            reset_at: bucket.reset_at,
+            key_id: Some(key_id.to_string()),
+        })
+    }
 
-            // Check retry_after
-            if let Some(retry_after) = info.retry_after {
-                if let Some(reset_at) = info.reset_at {
-                    let now = Instant::now();
-                    if now < reset_at {
-                        return Some(reset_at - now);
-                    }
-                } else {
-                    // No reset time, use retry_after as fallback
-                    return Some(retry_after);
-                }
-            }
+    /// Record a successful response for a key, resetting its backoff streak
+    pub fn record_success(&self, provider: &str, key_id: &str) {
+        let mut providers = self.providers.write();
+        if let Some(info) = providers.get_mut(&(provider.to_string(), key_id.to_string())) {
+            info.failure_count = 0;
+            info.prev_sleep = BASE_BACKOFF;
         }
+    }
 
-        None
+    /// Check if we should wait before making a request with this key.
+    /// `estimated_tokens`, if given, also checks the token bucket.
+    pub fn should_wait(
+        &self,
+        provider: &str,
+        key_id: &str,
+        estimated_tokens: Option<u32>,
+    ) -> Option<Duration> {
+        let providers = self.providers.read();
+        let info = providers.get(&(provider.to_string(), key_id.to_string()))?;
+
+        let wait = wait_duration_for(info, Instant::now(), estimated_tokens);
+        if wait.is_zero() {
+            None
+        } else {
+            Some(wait)
+        }
     }
 
-    /// Clear rate limit info for a provider
-    pub fn clear(&self, provider: &str) {
+    /// Would sending a request estimated to use `estimated_tokens` exceed
+    /// this key's remaining token budget? If so, returns the time until the
+    /// token bucket resets.
+    pub fn will_exceed_tokens(&self, provider: &str, key_id: &str, estimated_tokens: u32) -> Option<Duration> {
+        let providers = self.providers.read();
+        let info = providers.get(&(provider.to_string(), key_id.to_string()))?;
+        let bucket = info.buckets.get(&LimitType::TokensPerMinute)?;
+        let remaining = bucket.remaining?;
+
+        if estimated_tokens <= remaining {
+            return None;
+        }
+
+        let now = Instant::now();
+        match bucket.reset_at {
+            Some(reset_at) if now < reset_at => Some(reset_at - now),
+            _ => Some(Duration::ZERO),
+        }
+    }
+
+    /// Find the least-limited key for `provider` among `key_ids`: the one
+    /// that is available now (or soonest), with the shortest additional
+    /// wait. Returns `None` if `key_ids` is empty.
+    pub fn should_wait_any(
+        &self,
+        provider: &str,
+        key_ids: &[String],
+        estimated_tokens: Option<u32>,
+    ) -> Option<(String, Duration)> {
+        let providers = self.providers.read();
+        let now = Instant::now();
+
+        key_ids
+            .iter()
+            .map(|key_id| {
+                let wait = providers
+                    .get(&(provider.to_string(), key_id.clone()))
+                    .map(|info| wait_duration_for(info, now, estimated_tokens))
+                    .unwrap_or(Duration::ZERO);
+                (key_id.clone(), wait)
+            })
+            .min_by_key(|(_, wait)| *wait)
+    }
+
+    /// Proactively wait out any rate-limit window for `(provider, key_id)`
+    /// rather than only reacting to a 429 after the fact. Holds a per-key
+    /// FIFO queue (a binary `Semaphore`) so that when the window resets,
+    /// whichever caller on *this key* has been waiting longest goes first,
+    /// instead of every queued caller racing to recheck the window at once.
+    /// The gate is released as soon as the wait/reservation decision is
+    /// made — it does not stay held across the outbound request, so callers
+    /// using other keys (or other in-flight calls on this key, once their
+    /// own turn has been reserved) are never serialized behind it.
+    /// `estimated_tokens`, if given, also gates on the token bucket.
+    pub async fn acquire(&self, provider: &str, key_id: &str, estimated_tokens: Option<u32>) {
+        let semaphore = {
+            let mut gates = self.gates.write();
+            gates
+                .entry((provider.to_string(), key_id.to_string()))
+                .or_insert_with(|| Arc::new(Semaphore::new(1)))
+                .clone()
+        };
+
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("gate semaphore is never closed");
+
+        if let Some(wait) = self.should_wait(provider, key_id, estimated_tokens) {
+            tokio::time::sleep(wait).await;
+        }
+
+        self.reserve(provider, key_id, estimated_tokens);
+
+        drop(permit);
+    }
+
+    /// Synchronous counterpart to [`Self::acquire`] for the `blocking`
+    /// feature's `std::thread`-based client. Gates on the same window check
+    /// and reserves the same local counters, but, having no Tokio
+    /// `Semaphore` to queue on, doesn't serialize concurrent callers the way
+    /// `acquire` does — acceptable since the blocking client is meant to be
+    /// driven from a single thread.
+    #[cfg(feature = "blocking")]
+    pub fn acquire_blocking(&self, provider: &str, key_id: &str, estimated_tokens: Option<u32>) {
+        if let Some(wait) = self.should_wait(provider, key_id, estimated_tokens) {
+            std::thread::sleep(wait);
+        }
+
+        self.reserve(provider, key_id, estimated_tokens);
+    }
+
+    /// Clear rate limit info for a provider/key pair
+    pub fn clear(&self, provider: &str, key_id: &str) {
         let mut providers = self.providers.write();
-        providers.remove(provider);
+        providers.remove(&(provider.to_string(), key_id.to_string()));
     }
 
     /// Detect if a response indicates a rate limit error
@@ -164,6 +370,160 @@ impl RateLimitTracker {
     }
 }
 
+/// Compute how long to wait given a single key's tracked rate limit state,
+/// returning `Duration::ZERO` if no wait is needed right now. A request is
+/// blocked if *any* bucket is exhausted, so this returns the maximum wait
+/// across every tracked bucket, folding in the `estimated_tokens` budget
+/// check for the token bucket specifically.
+fn wait_duration_for(info: &ProviderRateLimit, now: Instant, estimated_tokens: Option<u32>) -> Duration {
+    let mut wait = Duration::ZERO;
+
+    for (limit_type, bucket) in &info.buckets {
+        // Check if we're at zero remaining quota for this window
+        if bucket.remaining == Some(0) {
+            if let Some(reset_at) = bucket.reset_at {
+                if now < reset_at {
+                    wait = wait.max(reset_at - now);
+                }
+            }
+        }
+
+        // Check retry_after
+        if let Some(retry_after) = bucket.retry_after {
+            if let Some(reset_at) = bucket.reset_at {
+                if now < reset_at {
+                    wait = wait.max(reset_at - now);
+                }
+            } else {
+                // No reset time, use retry_after as fallback
+                wait = wait.max(retry_after);
+            }
+        }
+
+        // Check the token bucket specifically, if the caller supplied an estimate
+        if *limit_type == LimitType::TokensPerMinute {
+            if let (Some(estimated), Some(remaining)) = (estimated_tokens, bucket.remaining) {
+                if estimated > remaining {
+                    if let Some(reset_at) = bucket.reset_at {
+                        if now < reset_at {
+                            wait = wait.max(reset_at - now);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    wait
+}
+
+/// Parse `limit_key`/`remaining_key`/`reset_key` headers into a single
+/// bucket's state, leaving each field untouched if its header isn't present
+/// in this response.
+fn update_bucket_from_headers(
+    bucket: &mut BucketState,
+    headers: &HeaderMap,
+    limit_key: &str,
+    remaining_key: &str,
+    reset_key: &str,
+) {
+    if let Some(value) = headers.get(limit_key) {
+        if let Ok(s) = value.to_str() {
+            if let Ok(n) = s.parse::<u64>() {
+                bucket.limit = Some(n);
+            }
+        }
+    }
+
+    if let Some(value) = headers.get(remaining_key) {
+        if let Ok(s) = value.to_str() {
+            if let Ok(n) = s.parse::<u32>() {
+                bucket.remaining = Some(n);
+            }
+        }
+    }
+
+    if let Some(value) = headers.get(reset_key) {
+        if let Ok(s) = value.to_str() {
+            // Try parsing as seconds
+            if let Ok(secs) = s.parse::<u64>() {
+                bucket.reset_at = Some(Instant::now() + Duration::from_secs(secs));
+            }
+            // Try parsing as duration string (e.g., "1m30s")
+            else if let Some(duration) = parse_duration_string(s) {
+                bucket.reset_at = Some(Instant::now() + duration);
+            }
+        }
+    }
+}
+
+/// Parse a `Retry-After` header value, which per RFC 9110 is either a plain
+/// second count or an HTTP-date (e.g. `"Wed, 21 Oct 2026 07:28:00 GMT"`).
+fn parse_retry_after(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    s.parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+        .or_else(|| parse_http_date(s))
+}
+
+/// Parse an RFC 1123 HTTP-date into the `Duration` remaining until then,
+/// relative to now (zero if it's already in the past). This is the only
+/// date format `Retry-After` uses, so a simple fixed-format parse is enough
+/// and avoids pulling in a date/time crate for one header.
+fn parse_http_date(s: &str) -> Option<Duration> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let [_weekday, day, month, year, time, "GMT"] = parts[..] else {
+        return None;
+    };
+
+    let day: u64 = day.parse().ok()?;
+    let month = match month {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: u64 = year.parse().ok()?;
+
+    let mut time_parts = time.split(':');
+    let hour: u64 = time_parts.next()?.parse().ok()?;
+    let minute: u64 = time_parts.next()?.parse().ok()?;
+    let second: u64 = time_parts.next()?.parse().ok()?;
+
+    let is_leap_year = |y: u64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+    let days_in_month = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+    let mut days = 0u64;
+    for y in 1970..year {
+        days += if is_leap_year(y) { 366 } else { 365 };
+    }
+    for m in 1..month {
+        days += days_in_month[(m - 1) as usize];
+        if m == 2 && is_leap_year(year) {
+            days += 1;
+        }
+    }
+    days += day.saturating_sub(1);
+
+    let target_secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+
+    Some(Duration::from_secs(target_secs.saturating_sub(now_secs)))
+}
+
 /// Parse a duration string like "1m30s" or "2h" into a Duration
 fn parse_duration_string(s: &str) -> Option<Duration> {
     let s = s.trim();
@@ -261,16 +621,319 @@ mod tests {
         let tracker = RateLimitTracker::new();
 
         // Initially no wait
-        assert!(tracker.should_wait("test").is_none());
+        assert!(tracker.should_wait("test", "key1", None).is_none());
 
         // Simulate rate limit
         let mut headers = HeaderMap::new();
         headers.insert("retry-after", "5".parse().unwrap());
 
-        let duration = tracker.update_from_rate_limit_error("test", &headers, None);
+        let duration = tracker.update_from_rate_limit_error("test", "key1", &headers, None);
         assert_eq!(duration, Duration::from_secs(5));
 
         // Should now need to wait
-        assert!(tracker.should_wait("test").is_some());
+        assert!(tracker.should_wait("test", "key1", None).is_some());
+    }
+
+    #[test]
+    fn test_retry_after_acts_as_floor_on_backoff() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "30".parse().unwrap());
+
+        // Decorrelated jitter alone starts out well under 30s, so the
+        // header-provided floor should win on the very first failure.
+        let duration = tracker.update_from_rate_limit_error("test", "key1", &headers, None);
+        assert!(duration >= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_record_success_resets_backoff_state() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "5".parse().unwrap());
+        tracker.update_from_rate_limit_error("test", "key1", &headers, None);
+
+        tracker.record_success("test", "key1");
+
+        let providers = tracker.providers.read();
+        let info = providers.get(&("test".to_string(), "key1".to_string())).unwrap();
+        assert_eq!(info.failure_count, 0);
+        assert_eq!(info.prev_sleep, BASE_BACKOFF);
+    }
+
+    #[test]
+    fn test_should_wait_any_picks_unlimited_key() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "30".parse().unwrap());
+        tracker.update_from_rate_limit_error("test", "key1", &headers, None);
+
+        let key_ids = vec!["key1".to_string(), "key2".to_string()];
+        let (key_id, wait) = tracker.should_wait_any("test", &key_ids, None).unwrap();
+
+        assert_eq!(key_id, "key2");
+        assert_eq!(wait, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_should_wait_any_returns_shortest_wait_when_all_limited() {
+        let tracker = RateLimitTracker::new();
+
+        let mut short_wait = HeaderMap::new();
+        short_wait.insert("retry-after", "5".parse().unwrap());
+        tracker.update_from_rate_limit_error("test", "key1", &short_wait, None);
+
+        let mut long_wait = HeaderMap::new();
+        long_wait.insert("retry-after", "50".parse().unwrap());
+        tracker.update_from_rate_limit_error("test", "key2", &long_wait, None);
+
+        let key_ids = vec!["key1".to_string(), "key2".to_string()];
+        let (key_id, wait) = tracker.should_wait_any("test", &key_ids, None).unwrap();
+
+        assert_eq!(key_id, "key1");
+        assert!(wait < Duration::from_secs(50));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_serializes_same_key_callers() {
+        let tracker = Arc::new(RateLimitTracker::new());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "200ms".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        let t1 = tracker.clone();
+        let first = tokio::spawn(async move {
+            t1.acquire("test", "key1", None).await;
+        });
+
+        // Give `first` a head start so it wins the gate and is mid-sleep
+        // when the second caller on the *same* key shows up.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let t2 = tracker.clone();
+        let second = tokio::spawn(async move {
+            t2.acquire("test", "key1", None).await;
+        });
+
+        // The second caller is queued behind the first on this key, so it
+        // shouldn't complete while the first is still waiting out the window.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!second.is_finished());
+
+        first.await.unwrap();
+        second.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_acquire_does_not_serialize_across_keys() {
+        let tracker = Arc::new(RateLimitTracker::new());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "200ms".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        let t1 = tracker.clone();
+        let _held = tokio::spawn(async move {
+            t1.acquire("test", "key1", None).await;
+        });
+
+        // Give key1's acquire a head start so it's mid-sleep when key2 shows up.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let start = Instant::now();
+        tracker.acquire("test", "key2", None).await;
+
+        // key2 has its own gate, so it must return immediately instead of
+        // queueing behind key1's in-flight wait the way a per-provider gate
+        // (keyed on `provider` alone) would force it to.
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_will_exceed_tokens_checks_remaining_budget() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-tokens", "100".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "30".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        assert!(tracker.will_exceed_tokens("test", "key1", 50).is_none());
+        let wait = tracker.will_exceed_tokens("test", "key1", 500).unwrap();
+        assert!(wait <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_should_wait_folds_in_token_budget() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-tokens", "100".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "30".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        assert!(tracker.should_wait("test", "key1", Some(50)).is_none());
+        assert!(tracker.should_wait("test", "key1", Some(500)).is_some());
+    }
+
+    #[test]
+    fn test_update_from_response_tracks_limit() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit-requests", "60".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "59".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        let providers = tracker.providers.read();
+        let bucket = providers
+            .get(&("test".to_string(), "key1".to_string()))
+            .unwrap()
+            .buckets
+            .get(&LimitType::RequestsPerMinute)
+            .unwrap();
+        assert_eq!(bucket.limit, Some(60));
+        assert_eq!(bucket.remaining, Some(59));
+    }
+
+    #[test]
+    fn test_update_from_response_tracks_daily_request_bucket() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit-requests-day", "10000".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests-day", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests-day", "3600".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        let providers = tracker.providers.read();
+        let bucket = providers
+            .get(&("test".to_string(), "key1".to_string()))
+            .unwrap()
+            .buckets
+            .get(&LimitType::RequestsPerDay)
+            .unwrap();
+        assert_eq!(bucket.limit, Some(10000));
+        assert_eq!(bucket.remaining, Some(0));
+        drop(providers);
+
+        // An exhausted daily bucket should block, same as any other bucket
+        assert!(tracker.should_wait("test", "key1", None).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_decrements_remaining_locally() {
+        let tracker = RateLimitTracker::new();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "2".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        // Two concurrent callers shouldn't both see the same pre-request
+        // count of 2 and think they're both clear to go.
+        let _first = tracker.acquire("test", "key1", None).await;
+        let _second = tracker.acquire("test", "key1", None).await;
+
+        let providers = tracker.providers.read();
+        let bucket = providers
+            .get(&("test".to_string(), "key1".to_string()))
+            .unwrap()
+            .buckets
+            .get(&LimitType::RequestsPerMinute)
+            .unwrap();
+        assert_eq!(bucket.remaining, Some(0));
+    }
+
+    #[test]
+    fn test_parse_retry_after_accepts_http_date() {
+        let now_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let future = now_secs + 120;
+
+        // A fixed, known instant far in the past relative to any date this
+        // crate will run: 2000-01-01T00:00:30Z, 946684830 seconds since epoch.
+        assert_eq!(
+            parse_http_date("Sat, 01 Jan 2000 00:00:30 GMT").map(|d| d.as_secs()),
+            Some(0)
+        );
+
+        let header = httpdate_for_test(future);
+        let parsed = parse_retry_after(&header).unwrap();
+        // Allow a couple seconds of slack for the time it takes to run this test.
+        assert!(parsed.as_secs() <= 120 && parsed.as_secs() >= 115);
+    }
+
+    /// Render a unix timestamp as an RFC 1123 HTTP-date, for round-tripping
+    /// through `parse_http_date` in tests without depending on a date crate.
+    fn httpdate_for_test(unix_secs: u64) -> String {
+        const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]; // epoch was a Thursday
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let is_leap_year = |y: u64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+        let days_in_month = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+        let mut days = unix_secs / 86_400;
+        let remaining = unix_secs % 86_400;
+        let (hour, minute, second) = (remaining / 3600, (remaining % 3600) / 60, remaining % 60);
+        let weekday = WEEKDAYS[(days % 7) as usize];
+
+        let mut year = 1970u64;
+        loop {
+            let year_len = if is_leap_year(year) { 366 } else { 365 };
+            if days < year_len {
+                break;
+            }
+            days -= year_len;
+            year += 1;
+        }
+
+        let mut month = 0usize;
+        loop {
+            let mut month_len = days_in_month[month];
+            if month == 1 && is_leap_year(year) {
+                month_len += 1;
+            }
+            if days < month_len {
+                break;
+            }
+            days -= month_len;
+            month += 1;
+        }
+
+        format!(
+            "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            weekday,
+            days + 1,
+            MONTHS[month],
+            year,
+            hour,
+            minute,
+            second
+        )
+    }
+
+    #[test]
+    fn test_should_wait_blocks_if_any_bucket_is_exhausted() {
+        let tracker = RateLimitTracker::new();
+
+        // Requests bucket has plenty left, but the token bucket is tapped out
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "100".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "15".parse().unwrap());
+        tracker.update_from_response("test", "key1", &headers, None, None);
+
+        // No token estimate given, so only the exhausted bucket itself blocks
+        let wait = tracker.should_wait("test", "key1", None).unwrap();
+        assert!(wait <= Duration::from_secs(15));
     }
 }
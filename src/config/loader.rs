@@ -2,6 +2,7 @@
 //!
 //! Handles loading and merging provider configurations from multiple sources.
 
+use crate::config::merge::Merge;
 use crate::config::provider::ProvidersConfig;
 use crate::error::{LlmaoError, Result};
 use std::collections::HashMap;
@@ -19,6 +20,10 @@ impl ConfigLoader {
             config: ProvidersConfig {
                 providers: HashMap::new(),
                 key_pools: HashMap::new(),
+                proxy: None,
+                model_routes: HashMap::new(),
+                server: None,
+                client: None,
             },
         };
 
@@ -31,12 +36,33 @@ impl ConfigLoader {
         Ok(loader)
     }
 
+    /// Create a new config loader, loading from default locations, and
+    /// validate the merged result. Returns an error describing every
+    /// violation found rather than just the first. Existing callers of
+    /// [`Self::new`] are unaffected since validation is opt-in.
+    pub fn new_validated() -> Result<Self> {
+        let loader = Self::new()?;
+        loader.config.validate().map_err(|errors| {
+            let joined = errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("; ");
+            LlmaoError::Config(format!("Invalid provider configuration: {}", joined))
+        })?;
+        Ok(loader)
+    }
+
     /// Create a loader with a specific config file
     pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
         let mut loader = Self {
             config: ProvidersConfig {
                 providers: HashMap::new(),
                 key_pools: HashMap::new(),
+                proxy: None,
+                model_routes: HashMap::new(),
+                server: None,
+                client: None,
             },
         };
 
@@ -71,7 +97,7 @@ impl ConfigLoader {
     }
 
     /// Get list of config paths to check
-    fn get_config_paths() -> Vec<PathBuf> {
+    pub(crate) fn get_config_paths() -> Vec<PathBuf> {
         let mut paths = Vec::new();
 
         // 1. Environment variable
@@ -111,15 +137,18 @@ impl ConfigLoader {
         Ok(())
     }
 
-    /// Merge another config into this one (later configs override earlier)
+    /// Merge another config into this one (later configs override earlier,
+    /// field-by-field rather than replacing whole entries)
     fn merge_config(&mut self, other: ProvidersConfig) {
-        for (name, provider) in other.providers {
-            self.config.providers.insert(name, provider);
-        }
+        self.config.merge(other);
+    }
 
-        for (name, pool) in other.key_pools {
-            self.config.key_pools.insert(name, pool);
-        }
+    /// Apply a typed override layer on top of everything already loaded, so
+    /// programmatic or CLI-supplied values outrank every file-based layer
+    /// without writing a temp file. This is the highest-precedence layer.
+    pub fn with_overrides(mut self, overrides: ProvidersConfig) -> Self {
+        self.config.merge(overrides);
+        self
     }
 
     /// Get the loaded configuration
@@ -139,6 +168,10 @@ impl Default for ConfigLoader {
             config: ProvidersConfig {
                 providers: HashMap::new(),
                 key_pools: HashMap::new(),
+                proxy: None,
+                model_routes: HashMap::new(),
+                server: None,
+                client: None,
             },
         })
     }
@@ -196,11 +229,17 @@ mod tests {
                     headers: HashMap::new(),
                     rate_limit: None,
                     special_handling: Default::default(),
+                    encoding: None,
+                    backend: None,
                 },
             )]
             .into_iter()
             .collect(),
             key_pools: HashMap::new(),
+            proxy: None,
+            model_routes: HashMap::new(),
+            server: None,
+            client: None,
         };
 
         loader.merge_config(custom);
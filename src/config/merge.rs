@@ -0,0 +1,219 @@
+//! Field-Level Config Merging
+//!
+//! Plain `HashMap` replacement loses information: merging a user-local file
+//! that only sets one field (say `rate_limit`) wipes out everything else a
+//! lower-precedence layer set for that provider. `Merge` combines two
+//! values of the same type field-by-field so layers can be purely
+//! additive: incoming `Some` overrides, `None` preserves, and map fields
+//! are unioned key-by-key.
+
+use super::provider::{KeyPoolConfig, ProviderConfig, ProvidersConfig, SpecialHandling};
+use std::collections::HashMap;
+
+/// Field-level merge of a higher-precedence layer into a lower one
+pub trait Merge {
+    /// Merge `other` into `self`, with `other`'s fields taking precedence
+    fn merge(&mut self, other: Self);
+}
+
+impl Merge for ProvidersConfig {
+    fn merge(&mut self, other: Self) {
+        self.providers.merge(other.providers);
+        self.key_pools.merge(other.key_pools);
+        if other.proxy.is_some() {
+            self.proxy = other.proxy;
+        }
+        if other.server.is_some() {
+            self.server = other.server;
+        }
+        if other.client.is_some() {
+            self.client = other.client;
+        }
+        for (key, value) in other.model_routes {
+            self.model_routes.insert(key, value);
+        }
+    }
+}
+
+impl<V: Merge> Merge for HashMap<String, V> {
+    fn merge(&mut self, other: Self) {
+        for (key, value) in other {
+            match self.get_mut(&key) {
+                Some(existing) => existing.merge(value),
+                None => {
+                    self.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+impl Merge for ProviderConfig {
+    fn merge(&mut self, other: Self) {
+        if !other.base_url.is_empty() {
+            self.base_url = other.base_url;
+        }
+        if other.api_key_env.is_some() {
+            self.api_key_env = other.api_key_env;
+        }
+        if other.api_keys_env.is_some() {
+            self.api_keys_env = other.api_keys_env;
+        }
+        if other.api_base_env.is_some() {
+            self.api_base_env = other.api_base_env;
+        }
+        if !other.models.is_empty() {
+            self.models = other.models;
+        }
+        for (key, value) in other.param_mappings {
+            self.param_mappings.insert(key, value);
+        }
+        for (key, value) in other.headers {
+            self.headers.insert(key, value);
+        }
+        if other.rate_limit.is_some() {
+            self.rate_limit = other.rate_limit;
+        }
+        self.special_handling.merge(other.special_handling);
+        if other.encoding.is_some() {
+            self.encoding = other.encoding;
+        }
+        if other.backend.is_some() {
+            self.backend = other.backend;
+        }
+    }
+}
+
+impl Merge for SpecialHandling {
+    fn merge(&mut self, other: Self) {
+        self.convert_content_list_to_string |= other.convert_content_list_to_string;
+        self.add_text_to_tool_calls |= other.add_text_to_tool_calls;
+        self.use_legacy_completions |= other.use_legacy_completions;
+    }
+}
+
+impl Merge for KeyPoolConfig {
+    fn merge(&mut self, other: Self) {
+        if !other.keys_env.is_empty() {
+            self.keys_env = other.keys_env;
+        }
+        if !other.keys.is_empty() {
+            self.keys = other.keys;
+        }
+        if other.rotation_strategy.is_some() {
+            self.rotation_strategy = other.rotation_strategy;
+        }
+        for (key, value) in other.key_metadata {
+            self.key_metadata.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::provider::RateLimitConfig;
+
+    fn base_provider() -> ProviderConfig {
+        ProviderConfig {
+            base_url: "https://api.example.com".to_string(),
+            api_key_env: Some("EXAMPLE_KEY".to_string()),
+            api_keys_env: None,
+            api_base_env: None,
+            models: vec!["model-a".to_string()],
+            param_mappings: HashMap::new(),
+            headers: HashMap::new(),
+            rate_limit: None,
+            special_handling: SpecialHandling::default(),
+            encoding: None,
+            backend: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_preserves_unset_fields() {
+        let mut base = base_provider();
+        let mut overlay = base_provider();
+        overlay.base_url = String::new();
+        overlay.api_key_env = None;
+        overlay.models = vec![];
+        overlay.rate_limit = Some(RateLimitConfig {
+            requests_per_minute: Some(60),
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        });
+
+        base.merge(overlay);
+
+        assert_eq!(base.base_url, "https://api.example.com");
+        assert_eq!(base.api_key_env, Some("EXAMPLE_KEY".to_string()));
+        assert_eq!(base.models, vec!["model-a".to_string()]);
+        assert_eq!(base.rate_limit.unwrap().requests_per_minute, Some(60));
+    }
+
+    #[test]
+    fn test_merge_unions_headers_and_param_mappings() {
+        let mut base = base_provider();
+        base.headers.insert("X-Base".to_string(), "1".to_string());
+        base.param_mappings.insert("from_base".to_string(), "to_base".to_string());
+
+        let mut overlay = base_provider();
+        overlay.headers = [("X-Overlay".to_string(), "2".to_string())].into_iter().collect();
+        overlay.param_mappings = [("from_overlay".to_string(), "to_overlay".to_string())]
+            .into_iter()
+            .collect();
+
+        base.merge(overlay);
+
+        assert_eq!(base.headers.get("X-Base"), Some(&"1".to_string()));
+        assert_eq!(base.headers.get("X-Overlay"), Some(&"2".to_string()));
+        assert_eq!(base.param_mappings.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_providers_map_is_field_level() {
+        let mut base_map = HashMap::new();
+        base_map.insert("openai".to_string(), base_provider());
+
+        let mut overlay_provider = base_provider();
+        overlay_provider.base_url = String::new();
+        overlay_provider.models = vec![];
+        overlay_provider.special_handling.add_text_to_tool_calls = true;
+
+        let mut overlay_map = HashMap::new();
+        overlay_map.insert("openai".to_string(), overlay_provider);
+
+        base_map.merge(overlay_map);
+
+        let merged = &base_map["openai"];
+        assert_eq!(merged.base_url, "https://api.example.com");
+        assert!(merged.special_handling.add_text_to_tool_calls);
+    }
+
+    #[test]
+    fn test_key_pool_merge_preserves_rotation_strategy_when_unset() {
+        use crate::config::provider::RotationStrategy;
+
+        let mut base = KeyPoolConfig {
+            keys_env: vec!["BASE_KEY".to_string()],
+            keys: vec![],
+            rotation_strategy: Some(RotationStrategy::Weighted),
+            key_metadata: HashMap::new(),
+        };
+
+        let overlay = KeyPoolConfig {
+            keys_env: vec![],
+            keys: vec!["extra-literal-key".to_string()],
+            rotation_strategy: None,
+            key_metadata: HashMap::new(),
+        };
+
+        base.merge(overlay);
+
+        assert_eq!(base.rotation_strategy, Some(RotationStrategy::Weighted));
+        assert_eq!(base.keys, vec!["extra-literal-key".to_string()]);
+    }
+}
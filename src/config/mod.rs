@@ -3,10 +3,16 @@
 //! Handles provider configuration loading and validation.
 
 pub mod loader;
+pub mod merge;
 pub mod provider;
+pub mod validate;
+pub mod watch;
 
 pub use loader::ConfigLoader;
+pub use merge::Merge;
 pub use provider::{
-    KeyPoolConfig, ProviderConfig, ProvidersConfig, RateLimitConfig, RotationStrategy,
-    SpecialHandling,
+    ClientConfig, KeyMetadata, KeyPoolConfig, ProviderConfig, ProvidersConfig, ProxyConfig,
+    RateLimitConfig, RotationStrategy, ServerConfig, SpecialHandling,
 };
+pub use validate::ConfigError;
+pub use watch::WatchHandle;
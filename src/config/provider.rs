@@ -14,6 +14,24 @@ pub struct ProvidersConfig {
     /// Optional key pool configurations
     #[serde(default)]
     pub key_pools: HashMap<String, KeyPoolConfig>,
+
+    /// Outbound proxy configuration, applied to every provider's requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+
+    /// Cross-provider failover chains: a model alias mapped to an ordered
+    /// list of concrete `provider/model` targets, tried in turn on rate
+    /// limiting or retriable HTTP errors.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_routes: HashMap<String, Vec<String>>,
+
+    /// Settings for the built-in OpenAI-compatible proxy server (`LLMClient.serve()`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerConfig>,
+
+    /// Transport-level settings for the HTTP client, e.g. response compression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientConfig>,
 }
 
 /// Configuration for a single LLM provider
@@ -53,6 +71,16 @@ pub struct ProviderConfig {
     /// Special handling flags
     #[serde(default, skip_serializing_if = "SpecialHandling::is_default")]
     pub special_handling: SpecialHandling,
+
+    /// Tokenizer encoding name (e.g. "cl100k_base"), falls back to a default encoding if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Backend tag selecting how requests/responses are translated for this
+    /// provider (e.g. "anthropic", "cohere", "gemini"); defaults to
+    /// OpenAI-compatible handling if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
 }
 
 /// Rate limit configuration for a provider
@@ -77,6 +105,12 @@ pub struct RateLimitConfig {
     /// Custom header for rate limit reset time
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reset_header: Option<String>,
+
+    /// Ceiling for a key's adaptive backoff when a provider returns a
+    /// rate-limit error without a `retry-after` duration (default: 5
+    /// minutes). See `ApiKey::mark_rate_limited`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backoff_secs: Option<u64>,
 }
 
 /// Key pool configuration for multi-key support
@@ -90,9 +124,148 @@ pub struct KeyPoolConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub keys: Vec<String>,
 
-    /// Rotation strategy
-    #[serde(default)]
-    pub rotation_strategy: RotationStrategy,
+    /// Rotation strategy. `None` means unset, so merging this layer in
+    /// preserves whatever a lower-precedence layer already configured
+    /// (falling back to `RotationStrategy::RoundRobin` only if no layer
+    /// ever set it) instead of resetting it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_strategy: Option<RotationStrategy>,
+
+    /// Per-key scoping, keyed by the key's literal value (an entry in `keys`
+    /// or a resolved `keys_env` value). Keys absent here are unrestricted:
+    /// any model, no expiry.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub key_metadata: HashMap<String, KeyMetadata>,
+}
+
+/// Scoping metadata for a single pooled API key: which models it may serve,
+/// and when (if ever) it stops being usable. Lets a broad key and several
+/// narrowly-scoped keys (e.g. one key only permitted for `gpt-4o`) share the
+/// same pool and be routed between automatically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    /// Model name patterns this key may be used for, matched against the
+    /// requested `ModelRoute::model_id()`. An entry ending in `*` matches by
+    /// prefix; otherwise it must match exactly. Empty means unrestricted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_models: Vec<String>,
+
+    /// When this key stops being usable, if ever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<std::time::SystemTime>,
+
+    /// Explicit weight for `RotationStrategy::Weighted`, overriding the
+    /// default of inferring it from `RateLimitConfig.requests_per_minute`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+}
+
+/// Outbound proxy configuration for the HTTP client. An explicit `url` wins;
+/// otherwise the standard `HTTP_PROXY`/`HTTPS_PROXY`/`ALL_PROXY` environment
+/// variables are consulted in that order. Both `http://` and `socks5://`
+/// URLs are supported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Explicit proxy URL, e.g. `http://proxy.example.com:8080` or
+    /// `socks5://127.0.0.1:1080`. Falls back to the environment if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Hosts that bypass the proxy, matching the `NO_PROXY` convention
+    /// (exact hostnames or `.suffix` wildcards). Falls back to the
+    /// `NO_PROXY`/`no_proxy` environment variable (comma-separated) if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// Override the connect timeout (seconds) used when routing through the proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+}
+
+impl ProxyConfig {
+    /// Resolve the effective proxy URL: the explicit `url`, else the first
+    /// of `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` that's set in the environment.
+    pub fn resolve_url(&self) -> Option<String> {
+        self.url.clone().or_else(|| {
+            ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"]
+                .iter()
+                .find_map(|var| std::env::var(var).ok())
+        })
+    }
+
+    /// Resolve the no-proxy allowlist: the explicit `no_proxy` list, else the
+    /// `NO_PROXY`/`no_proxy` environment variable split on commas.
+    pub fn resolve_no_proxy(&self) -> Vec<String> {
+        if !self.no_proxy.is_empty() {
+            return self.no_proxy.clone();
+        }
+        std::env::var("NO_PROXY")
+            .or_else(|_| std::env::var("no_proxy"))
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Settings for the built-in OpenAI-compatible proxy server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address to bind
+    pub host: String,
+
+    /// Port to bind
+    pub port: u16,
+
+    /// Log the fully reassembled message (via `StreamAccumulator`) for each
+    /// streamed request once it completes
+    pub log_completions: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            log_completions: false,
+        }
+    }
+}
+
+/// Transport-level settings for the HTTP client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Negotiate response compression with providers by sending an
+    /// `Accept-Encoding` header for `compression_encodings` and
+    /// transparently decompressing the response. Disable for providers
+    /// that mishandle compressed SSE bodies.
+    pub compression: bool,
+
+    /// Encodings to negotiate when `compression` is enabled. Recognized
+    /// values: `"gzip"`, `"deflate"`, `"zstd"`. Unrecognized values are ignored.
+    pub compression_encodings: Vec<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            compression: true,
+            compression_encodings: vec!["gzip".to_string(), "deflate".to_string(), "zstd".to_string()],
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Whether `encoding` (`"gzip"`, `"deflate"`, or `"zstd"`) should be negotiated
+    pub fn wants(&self, encoding: &str) -> bool {
+        self.compression && self.compression_encodings.iter().any(|e| e == encoding)
+    }
 }
 
 /// Strategy for rotating API keys
@@ -108,6 +281,10 @@ pub enum RotationStrategy {
 
     /// Random selection
     Random,
+
+    /// Random selection weighted by each key's capacity, so a high-quota
+    /// key receives proportionally more traffic than a low-quota one
+    Weighted,
 }
 
 /// Special handling flags for provider-specific quirks
@@ -180,12 +357,54 @@ impl ProviderConfig {
             }
         }
     }
+
+    /// Resolve the tokenizer encoding for this provider, falling back to the
+    /// default cl100k-style encoding if unset or unrecognized
+    pub fn get_encoding(&self) -> crate::tokenizer::Encoding {
+        self.encoding
+            .as_deref()
+            .and_then(crate::tokenizer::Encoding::from_name)
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_server_config_defaults() {
+        let config = ServerConfig::default();
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 8080);
+        assert!(!config.log_completions);
+    }
+
+    #[test]
+    fn test_deserialize_server_config_applies_defaults_to_missing_fields() {
+        let config: ServerConfig = serde_json::from_str(r#"{"port": 3000}"#).unwrap();
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 3000);
+    }
+
+    #[test]
+    fn test_client_config_defaults_enable_compression() {
+        let config = ClientConfig::default();
+        assert!(config.compression);
+        assert!(config.wants("gzip"));
+        assert!(config.wants("zstd"));
+        assert!(!config.wants("br"));
+    }
+
+    #[test]
+    fn test_client_config_disabled_wants_nothing() {
+        let config = ClientConfig {
+            compression: false,
+            ..ClientConfig::default()
+        };
+        assert!(!config.wants("gzip"));
+    }
+
     #[test]
     fn test_deserialize_provider_config() {
         let json = r#"{
@@ -224,6 +443,8 @@ mod tests {
             headers: HashMap::new(),
             rate_limit: None,
             special_handling: SpecialHandling::default(),
+            encoding: None,
+            backend: None,
         };
 
         let mut params = serde_json::json!({
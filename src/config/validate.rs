@@ -0,0 +1,296 @@
+//! Configuration Validation
+//!
+//! Walks a loaded `ProvidersConfig` and collects *all* violations instead of
+//! surfacing just the first one as a confusing runtime failure.
+
+use crate::config::provider::{KeyPoolConfig, ProviderConfig, ProvidersConfig};
+use std::fmt;
+
+/// A single configuration violation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Name of the offending provider or key pool
+    pub name: String,
+
+    /// Field within that entry that failed validation
+    pub field: String,
+
+    /// Human-readable description of the violation
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(name: impl Into<String>, field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}: {}", self.name, self.field, self.message)
+    }
+}
+
+impl ProvidersConfig {
+    /// Validate every provider and key pool, collecting all violations
+    /// rather than stopping at the first one.
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        for (name, provider) in &self.providers {
+            validate_provider(name, provider, &mut errors);
+        }
+
+        for (name, pool) in &self.key_pools {
+            validate_key_pool(name, pool, &self.providers, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn validate_provider(name: &str, provider: &ProviderConfig, errors: &mut Vec<ConfigError>) {
+    if provider.base_url.trim().is_empty() {
+        errors.push(ConfigError::new(name, "base_url", "must not be empty"));
+    } else if !provider.base_url.starts_with("http://") && !provider.base_url.starts_with("https://") {
+        errors.push(ConfigError::new(
+            name,
+            "base_url",
+            format!("'{}' must be a valid http(s) URL", provider.base_url),
+        ));
+    }
+
+    if let Some(env_var) = &provider.api_key_env {
+        if env_var.trim().is_empty() {
+            errors.push(ConfigError::new(name, "api_key_env", "must not be empty if set"));
+        }
+    }
+
+    if let Some(env_vars) = &provider.api_keys_env {
+        if env_vars.iter().any(|v| v.trim().is_empty()) {
+            errors.push(ConfigError::new(
+                name,
+                "api_keys_env",
+                "must not contain empty environment variable names",
+            ));
+        }
+    }
+
+    if let Some(rate_limit) = &provider.rate_limit {
+        if rate_limit.requests_per_minute == Some(0) {
+            errors.push(ConfigError::new(
+                name,
+                "rate_limit.requests_per_minute",
+                "must be greater than zero if set",
+            ));
+        }
+        if rate_limit.tokens_per_minute == Some(0) {
+            errors.push(ConfigError::new(
+                name,
+                "rate_limit.tokens_per_minute",
+                "must be greater than zero if set",
+            ));
+        }
+    }
+}
+
+fn validate_key_pool(
+    name: &str,
+    pool: &KeyPoolConfig,
+    providers: &std::collections::HashMap<String, ProviderConfig>,
+    errors: &mut Vec<ConfigError>,
+) {
+    if pool.keys.is_empty() && pool.keys_env.is_empty() {
+        errors.push(ConfigError::new(
+            name,
+            "keys",
+            "must define at least one key via `keys` or `keys_env`",
+        ));
+    }
+
+    if !providers.contains_key(name) {
+        errors.push(ConfigError::new(
+            name,
+            "name",
+            "references a provider that isn't defined in `providers`",
+        ));
+    }
+
+    // `key_metadata` is keyed by a key's literal value, so it can only be
+    // cross-checked against `keys` -- `keys_env` entries are environment
+    // variable *names*, not the secret values they resolve to, and aren't
+    // known at validation time. Skip the check when `keys_env` is set: a
+    // metadata entry that looks unmatched here may simply refer to a
+    // `keys_env`-resolved key we can't see yet.
+    if pool.keys_env.is_empty() {
+        for key in pool.key_metadata.keys() {
+            if !pool.keys.contains(key) {
+                errors.push(ConfigError::new(
+                    name,
+                    "key_metadata",
+                    format!("scopes key '{}', which isn't in this pool's `keys`", key),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::provider::{KeyMetadata, RateLimitConfig};
+    use std::collections::HashMap;
+
+    fn provider(base_url: &str) -> ProviderConfig {
+        ProviderConfig {
+            base_url: base_url.to_string(),
+            api_key_env: Some("TEST_KEY".to_string()),
+            api_keys_env: None,
+            api_base_env: None,
+            models: vec![],
+            param_mappings: HashMap::new(),
+            headers: HashMap::new(),
+            rate_limit: None,
+            special_handling: Default::default(),
+            encoding: None,
+            backend: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_config_passes() {
+        let config = ProvidersConfig {
+            providers: [("openai".to_string(), provider("https://api.openai.com/v1"))]
+                .into_iter()
+                .collect(),
+            key_pools: HashMap::new(),
+            proxy: None,
+            model_routes: HashMap::new(),
+            server: None,
+            client: None,
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_collects_all_violations() {
+        let mut bad_provider = provider("not-a-url");
+        bad_provider.rate_limit = Some(RateLimitConfig {
+            requests_per_minute: Some(0),
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        });
+
+        let config = ProvidersConfig {
+            providers: [("broken".to_string(), bad_provider)].into_iter().collect(),
+            key_pools: [(
+                "orphan_pool".to_string(),
+                KeyPoolConfig {
+                    keys_env: vec![],
+                    keys: vec![],
+                    rotation_strategy: None,
+                    key_metadata: HashMap::new(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            proxy: None,
+            model_routes: HashMap::new(),
+            server: None,
+            client: None,
+        };
+
+        let errors = config.validate().unwrap_err();
+        assert!(errors.iter().any(|e| e.name == "broken" && e.field == "base_url"));
+        assert!(errors
+            .iter()
+            .any(|e| e.name == "broken" && e.field == "rate_limit.requests_per_minute"));
+        assert!(errors.iter().any(|e| e.name == "orphan_pool" && e.field == "keys"));
+        assert!(errors.iter().any(|e| e.name == "orphan_pool" && e.field == "name"));
+    }
+
+    #[test]
+    fn test_key_metadata_referencing_unknown_key_is_rejected() {
+        let config = ProvidersConfig {
+            providers: [("openai".to_string(), provider("https://api.openai.com/v1"))]
+                .into_iter()
+                .collect(),
+            key_pools: [(
+                "openai".to_string(),
+                KeyPoolConfig {
+                    keys_env: vec![],
+                    keys: vec!["sk-live-1".to_string()],
+                    rotation_strategy: None,
+                    key_metadata: [(
+                        "sk-typo-d".to_string(),
+                        KeyMetadata {
+                            allowed_models: vec![],
+                            expires_at: None,
+                            weight: None,
+                        },
+                    )]
+                    .into_iter()
+                    .collect(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            proxy: None,
+            model_routes: HashMap::new(),
+            server: None,
+            client: None,
+        };
+
+        let errors = config.validate().unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|e| e.name == "openai" && e.field == "key_metadata"));
+    }
+
+    #[test]
+    fn test_key_metadata_skipped_when_keys_env_present() {
+        let config = ProvidersConfig {
+            providers: [("openai".to_string(), provider("https://api.openai.com/v1"))]
+                .into_iter()
+                .collect(),
+            key_pools: [(
+                "openai".to_string(),
+                KeyPoolConfig {
+                    keys_env: vec!["OPENAI_KEY_1".to_string()],
+                    keys: vec![],
+                    rotation_strategy: None,
+                    key_metadata: [(
+                        "whatever-the-env-var-resolves-to".to_string(),
+                        KeyMetadata {
+                            allowed_models: vec![],
+                            expires_at: None,
+                            weight: None,
+                        },
+                    )]
+                    .into_iter()
+                    .collect(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            proxy: None,
+            model_routes: HashMap::new(),
+            server: None,
+            client: None,
+        };
+
+        assert!(config.validate().is_ok());
+    }
+}
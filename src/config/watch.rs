@@ -0,0 +1,127 @@
+//! Configuration Hot-Reloading
+//!
+//! Watches the provider config files on disk and atomically swaps a shared
+//! `ProvidersConfig` when they change, so long-running services can pick up
+//! edits without a restart.
+
+use crate::config::loader::ConfigLoader;
+use crate::config::provider::ProvidersConfig;
+use crate::error::{LlmaoError, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::broadcast;
+
+/// Capacity of the reload-error broadcast channel
+const ERROR_CHANNEL_CAPACITY: usize = 16;
+
+/// Handle to a running config watch. Dropping it stops the underlying watcher.
+pub struct WatchHandle {
+    /// Kept alive for as long as the watch should run
+    _watcher: RecommendedWatcher,
+
+    /// Broadcasts a message whenever a reload fails
+    errors: broadcast::Sender<String>,
+}
+
+impl WatchHandle {
+    /// Subscribe to reload errors (e.g. malformed config files). When a
+    /// reload fails the last-known-good config keeps serving and the error
+    /// is broadcast here instead of poisoning shared state.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<String> {
+        self.errors.subscribe()
+    }
+}
+
+impl ConfigLoader {
+    /// Start watching every path returned by [`Self::get_config_paths`] that
+    /// exists when this is called for modification; paths that do not exist
+    /// yet are not watched. On change, re-runs the same builtin-defaults-then-files
+    /// merge pipeline used by [`Self::new`] and atomically swaps the shared
+    /// config behind the returned `RwLock`, preserving precedence (later
+    /// files override earlier, builtins lowest).
+    pub fn watch() -> Result<(Arc<RwLock<ProvidersConfig>>, WatchHandle)> {
+        let initial = ConfigLoader::new()?.into_config();
+        let shared = Arc::new(RwLock::new(initial));
+        let (error_tx, _) = broadcast::channel(ERROR_CHANNEL_CAPACITY);
+
+        let (event_tx, event_rx) = mpsc::channel::<notify::Result<Event>>();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+        })
+        .map_err(|e| LlmaoError::Config(format!("Failed to start config watcher: {}", e)))?;
+
+        // Watch each config file's *parent directory* rather than the file
+        // itself. Editors and deploy tooling (vim, atomic-rename scripts,
+        // ConfigMap symlink swaps) replace a file by writing a temp file and
+        // renaming it over the original, which orphans an inotify watch held
+        // on the old inode -- the watch would silently stop firing after the
+        // very first edit. Watching the directory survives the rename; we
+        // filter events back down to just the filenames we care about below.
+        let mut watched_names = HashSet::new();
+        let mut watched_dirs = HashSet::new();
+        for path in Self::get_config_paths() {
+            if !path.exists() {
+                continue;
+            }
+            if let Some(name) = path.file_name() {
+                watched_names.insert(name.to_os_string());
+            }
+            if let Some(dir) = path.parent() {
+                if watched_dirs.insert(dir.to_path_buf()) {
+                    // Best-effort: a directory that disappears after this check
+                    // simply won't be watched until the next `watch()` call.
+                    let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+                }
+            }
+        }
+
+        let shared_for_thread = shared.clone();
+        let errors_for_thread = error_tx.clone();
+        thread::spawn(move || {
+            for event in event_rx {
+                let Ok(event) = event else { continue };
+
+                if !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    continue;
+                }
+
+                if !event_touches_watched_file(&event, &watched_names) {
+                    continue;
+                }
+
+                match ConfigLoader::new() {
+                    Ok(loader) => {
+                        *shared_for_thread.write() = loader.into_config();
+                    }
+                    Err(e) => {
+                        let _ = errors_for_thread.send(format!("Failed to reload config: {}", e));
+                    }
+                }
+            }
+        });
+
+        Ok((
+            shared,
+            WatchHandle {
+                _watcher: watcher,
+                errors: error_tx,
+            },
+        ))
+    }
+}
+
+/// Whether `event` touched one of the filenames we actually care about.
+/// Watching a directory picks up every file within it, so this filters that
+/// back down to the config files [`ConfigLoader::watch`] found on startup.
+fn event_touches_watched_file(event: &Event, watched_names: &HashSet<OsString>) -> bool {
+    event
+        .paths
+        .iter()
+        .filter_map(|p| p.file_name())
+        .any(|name| watched_names.contains(name))
+}
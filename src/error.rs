@@ -2,9 +2,44 @@
 //!
 //! Comprehensive error handling for the LLM client library.
 
+use crate::client::rate_limiter::LimitType;
 use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use std::fmt;
+use std::time::Instant;
+
+/// Where a `RateLimited` error's computed retry delay came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetrySource {
+    /// Taken directly from a provider-supplied header (e.g. `retry-after`)
+    Header,
+
+    /// Computed via decorrelated-jitter backoff, either because no header was
+    /// present or because the backoff was longer than the header's delay
+    Backoff,
+
+    /// No bucket or header info was available; a fixed fallback was used
+    Default,
+}
+
+/// Structured retry metadata for a `RateLimited` error: which bucket
+/// tripped, where the delay came from, and (if known) when it resets and
+/// which key it applies to. Lets callers implement their own scheduling
+/// instead of just getting a bare second count.
+#[derive(Debug, Clone)]
+pub struct RetryInfo {
+    /// Which window tripped
+    pub bucket: LimitType,
+
+    /// Whether the delay came from a header or was computed
+    pub source: RetrySource,
+
+    /// When the bucket is expected to reset, if known
+    pub reset_at: Option<Instant>,
+
+    /// Which API key the limit applies to, if known
+    pub key_id: Option<String>,
+}
 
 /// Main error type for LLMAO operations
 #[derive(Debug)]
@@ -25,6 +60,11 @@ pub enum LlmaoError {
     RateLimited {
         provider: String,
         retry_after: Option<u64>,
+
+        /// Which bucket tripped, where the delay came from, and other
+        /// detail for callers that want to schedule their own retry
+        /// rather than just sleeping for `retry_after` seconds
+        retry_info: Option<RetryInfo>,
     },
 
     /// HTTP request failed
@@ -44,6 +84,23 @@ pub enum LlmaoError {
 
     /// Generic internal error
     Internal(String),
+
+    /// A tool call referenced a function name with no registered handler
+    ToolNotFound(String),
+
+    /// A `may_`-prefixed tool call was denied by the confirmation callback
+    ToolExecutionDenied(String),
+
+    /// A tool call failed while running concurrently with other calls in
+    /// the same turn; tagged with the failing call's id so the caller
+    /// knows which of several parallel calls to blame.
+    ToolCallFailed { tool_call_id: String, message: String },
+
+    /// Every target in a cross-provider failover chain failed
+    AllTargetsFailed {
+        model: String,
+        errors: Vec<(String, LlmaoError)>,
+    },
 }
 
 impl fmt::Display for LlmaoError {
@@ -75,6 +132,7 @@ impl fmt::Display for LlmaoError {
             LlmaoError::RateLimited {
                 provider,
                 retry_after,
+                ..
             } => {
                 if let Some(seconds) = retry_after {
                     write!(
@@ -104,6 +162,27 @@ impl fmt::Display for LlmaoError {
                     msg
                 )
             }
+            LlmaoError::ToolNotFound(name) => {
+                write!(f, "No handler registered for tool '{}'", name)
+            }
+            LlmaoError::ToolExecutionDenied(name) => {
+                write!(f, "Execution of tool '{}' was denied", name)
+            }
+            LlmaoError::ToolCallFailed { tool_call_id, message } => {
+                write!(f, "Tool call '{}' failed: {}", tool_call_id, message)
+            }
+            LlmaoError::AllTargetsFailed { model, errors } => {
+                let joined = errors
+                    .iter()
+                    .map(|(target, e)| format!("{}: {}", target, e))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                write!(
+                    f,
+                    "All failover targets for model '{}' failed: {}",
+                    model, joined
+                )
+            }
         }
     }
 }
@@ -144,13 +223,47 @@ impl From<LlmaoError> for PyErr {
             LlmaoError::ProviderNotFound(_) => PyValueError::new_err(msg),
             LlmaoError::ModelNotSupported { .. } => PyValueError::new_err(msg),
             LlmaoError::NoKeysAvailable(_) => PyRuntimeError::new_err(msg),
-            LlmaoError::RateLimited { .. } => PyRuntimeError::new_err(msg),
+            LlmaoError::RateLimited {
+                retry_after,
+                retry_info,
+                ..
+            } => {
+                let pyerr = PyRuntimeError::new_err(msg);
+                Python::with_gil(|py| {
+                    let value = pyerr.value_bound(py);
+                    let _ = value.setattr("retry_after", *retry_after);
+                    let _ = value.setattr(
+                        "bucket",
+                        retry_info.as_ref().map(|info| format!("{:?}", info.bucket)),
+                    );
+                    let _ = value.setattr(
+                        "retry_source",
+                        retry_info.as_ref().map(|info| format!("{:?}", info.source)),
+                    );
+                    let _ = value.setattr(
+                        "key_id",
+                        retry_info.as_ref().and_then(|info| info.key_id.clone()),
+                    );
+                });
+                pyerr
+            }
             LlmaoError::Request(_) => PyConnectionError::new_err(msg),
             LlmaoError::Response(_) => PyRuntimeError::new_err(msg),
             LlmaoError::Stream(_) => PyRuntimeError::new_err(msg),
             LlmaoError::Auth(_) => PyRuntimeError::new_err(format!("Auth error: {}", msg)),
             LlmaoError::Timeout(_) => PyConnectionError::new_err(msg),
             LlmaoError::Internal(_) => PyRuntimeError::new_err(msg),
+            LlmaoError::ToolNotFound(_) => PyValueError::new_err(msg),
+            LlmaoError::ToolExecutionDenied(_) => PyRuntimeError::new_err(msg),
+            LlmaoError::ToolCallFailed { tool_call_id, .. } => {
+                let pyerr = PyRuntimeError::new_err(msg);
+                Python::with_gil(|py| {
+                    let value = pyerr.value_bound(py);
+                    let _ = value.setattr("tool_call_id", tool_call_id);
+                });
+                pyerr
+            }
+            LlmaoError::AllTargetsFailed { .. } => PyRuntimeError::new_err(msg),
         }
     }
 }
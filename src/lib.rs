@@ -3,37 +3,67 @@
 //! A high-performance Python library written in Rust for unified LLM provider access
 //! with intelligent rate limiting and key rotation.
 
+use parking_lot::RwLock;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
+use pyo3::types::{PyDict, PyList, PyTuple};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
 
 pub mod api;
+pub mod backend;
 pub mod client;
 pub mod config;
 pub mod error;
 pub mod router;
+pub mod server;
+pub mod tokenizer;
+pub mod tools;
 
-use api::{CompletionRequest, CompletionResponse, Message, MessageContent};
+use api::{CompletionRequest, CompletionResponse, Message, MessageContent, ToolChoice, ToolChoiceFunction};
 use client::HttpClient;
 use config::{ConfigLoader, ProviderConfig};
 use error::{LlmaoError, Result};
 use router::{KeyPool, ModelRoute};
 
-/// The main LLM client
-pub struct LlmClient {
-    /// Provider registry (metadata from registry.json)
-    provider_registry: config::ProviderRegistry,
+/// Everything derived from a loaded config: the expanded model/provider maps
+/// plus the live key pools. Held behind a lock inside `LlmClient` so
+/// `reload`/`reload_from_path` can atomically swap it in without tearing
+/// down the client.
+struct ClientState {
+    /// Every configured provider, keyed by name. Already the merge of the
+    /// built-in `providers.json` defaults and every user config layer, so
+    /// there's no separate "registry vs custom" distinction to track.
+    providers: HashMap<String, ProviderConfig>,
+
+    /// Expanded model entries (provider/model -> that provider's config)
+    #[allow(dead_code)] // Will be used for model-specific configuration lookups
+    model_configs: HashMap<String, ProviderConfig>,
 
-    /// Fallback provider configs (from user config with custom base_url)
-    custom_providers: HashMap<String, ProviderConfig>,
+    /// API key pools per provider, shared via `Arc` so a reload can carry
+    /// a pool forward (preserving its rate-limit state) instead of
+    /// rebuilding it when the underlying key set hasn't changed.
+    key_pools: HashMap<String, Arc<KeyPool>>,
 
-    /// Expanded model configurations (provider/model -> config)
-    #[allow(dead_code)] // Will be used for model-specific configuration lookups
-    model_configs: HashMap<String, config::ModelConfig>,
+    /// The raw key list each pool in `key_pools` was built from, so a
+    /// reload can tell whether a provider's keys actually changed.
+    key_sources: HashMap<String, Vec<String>>,
+
+    /// Cross-provider failover chains: a model alias mapped to an ordered
+    /// list of concrete `provider/model` targets.
+    model_routes: HashMap<String, Vec<String>>,
 
-    /// API key pools per provider
-    key_pools: HashMap<String, KeyPool>,
+    /// Settings for the built-in proxy server, if configured
+    server: Option<config::ServerConfig>,
+}
+
+/// The main LLM client
+pub struct LlmClient {
+    /// Expanded config state, swapped atomically on reload
+    state: RwLock<Arc<ClientState>>,
+
+    /// Path the client was configured from, if any (used by `reload()`)
+    config_path: Option<String>,
 
     /// HTTP client
     http_client: HttpClient,
@@ -43,195 +73,334 @@ impl LlmClient {
     /// Create a new client with default configuration
     pub fn new() -> Result<Self> {
         let loader = ConfigLoader::new()?;
-        Self::from_loader(loader)
+        Self::from_loader(loader, None)
     }
 
     /// Create a client with a custom config path
     pub fn with_config_path(path: &str) -> Result<Self> {
         let loader = ConfigLoader::from_path(path)?;
-        Self::from_loader(loader)
+        Self::from_loader(loader, Some(path.to_string()))
+    }
+
+    /// Create a client from a config loader. `config_path` is remembered so
+    /// a later `reload()` (as opposed to `reload_from_path`) re-reads the
+    /// same source.
+    fn from_loader(loader: ConfigLoader, config_path: Option<String>) -> Result<Self> {
+        let http_client =
+            HttpClient::with_config(loader.config().proxy.as_ref(), loader.config().client.as_ref())?;
+        let state = Self::expand_config(&loader, None);
+
+        Ok(Self {
+            state: RwLock::new(Arc::new(state)),
+            config_path,
+            http_client,
+        })
     }
 
-    /// Create a client from a config loader
-    fn from_loader(loader: ConfigLoader) -> Result<Self> {
-        let provider_registry = loader.provider_registry().clone();
-        let user_config = loader.config().clone();
+    /// Re-run the expansion pipeline against `loader` and return the new
+    /// state. When `previous` is given, a provider whose key list hasn't
+    /// changed keeps its existing `KeyPool` (and thus its live rate-limit
+    /// state) instead of getting a fresh one.
+    fn expand_config(loader: &ConfigLoader, previous: Option<&ClientState>) -> ClientState {
+        let config = loader.config();
+        let model_routes = config.model_routes.clone();
+        let server = config.server.clone();
 
-        // Expand user config into individual model configurations
         let mut model_configs = HashMap::new();
-        let mut key_pools = HashMap::new();
-        let mut custom_providers: HashMap<String, ProviderConfig> = HashMap::new();
-
-        for (key, model_config) in user_config {
-            // Check if key contains "/" (specific model) or not (provider-level)
-            if key.contains('/') {
-                // Specific model: "provider/model"
-                let parts: Vec<&str> = key.splitn(2, '/').collect();
-                let provider_name = parts[0];
-
-                // Create key pool for this provider if not exists
-                if !key_pools.contains_key(provider_name) && !model_config.keys.is_empty() {
-                    key_pools.insert(
-                        provider_name.to_string(),
-                        KeyPool::new(
-                            provider_name.to_string(),
-                            model_config.keys.clone(),
-                            model_config.rotation_strategy.clone(),
-                        ),
-                    );
-                }
+        let mut key_pools: HashMap<String, Arc<KeyPool>> = HashMap::new();
+        let mut key_sources: HashMap<String, Vec<String>> = HashMap::new();
+        let empty_metadata: HashMap<String, config::KeyMetadata> = HashMap::new();
 
-                // If this provider is not in registry and has a base_url, create a custom provider entry
-                if !provider_registry.contains_key(provider_name) {
-                    if let Some(base_url) = &model_config.base_url {
-                        if !custom_providers.contains_key(provider_name) {
-                            custom_providers.insert(
-                                provider_name.to_string(),
-                                ProviderConfig {
-                                    base_url: base_url.clone(),
-                                    api_key_env: None,
-                                    api_keys_env: None,
-                                    api_base_env: None,
-                                    models: vec![],
-                                    param_mappings: model_config.param_mappings.clone(),
-                                    headers: model_config.headers.clone(),
-                                    rate_limit: model_config.rate_limit.clone(),
-                                    special_handling: Default::default(),
-                                },
-                            );
-                        }
-                    }
+        for (provider_name, provider_config) in &config.providers {
+            for model_name in &provider_config.models {
+                model_configs.insert(format!("{}/{}", provider_name, model_name), provider_config.clone());
+            }
+
+            let pool_config = config.key_pools.get(provider_name);
+            let keys = Self::resolve_keys(pool_config, provider_config);
+            let strategy = pool_config.and_then(|p| p.rotation_strategy.clone()).unwrap_or_default();
+            let key_metadata = pool_config.map(|p| &p.key_metadata).unwrap_or(&empty_metadata);
+
+            Self::insert_pool(
+                &mut key_pools,
+                &mut key_sources,
+                previous,
+                provider_name,
+                &keys,
+                &strategy,
+                provider_config.rate_limit.as_ref(),
+                key_metadata,
+            );
+        }
+
+        ClientState {
+            providers: config.providers.clone(),
+            model_configs,
+            key_pools,
+            key_sources,
+            model_routes,
+            server,
+        }
+    }
+
+    /// Resolve the actual key values for `provider_name`: the pool's
+    /// `keys_env` (resolved from the environment) followed by its literal
+    /// `keys`, deduplicated; or, if no key pool is configured for this
+    /// provider, the single/multi env vars on the provider config itself.
+    fn resolve_keys(pool_config: Option<&config::KeyPoolConfig>, provider: &ProviderConfig) -> Vec<String> {
+        let Some(pool_config) = pool_config else {
+            return provider.get_api_keys();
+        };
+
+        let mut keys = Vec::new();
+        for env_var in &pool_config.keys_env {
+            if let Ok(key) = std::env::var(env_var) {
+                if !keys.contains(&key) {
+                    keys.push(key);
                 }
+            }
+        }
+        for key in &pool_config.keys {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
 
-                // Store model config
-                model_configs.insert(key.clone(), model_config);
+    /// Build (or reuse, if `previous` has an unchanged key set for this
+    /// provider) a `KeyPool` and record it in `key_pools`/`key_sources`.
+    fn insert_pool(
+        key_pools: &mut HashMap<String, Arc<KeyPool>>,
+        key_sources: &mut HashMap<String, Vec<String>>,
+        previous: Option<&ClientState>,
+        provider_name: &str,
+        keys: &[String],
+        strategy: &config::RotationStrategy,
+        rate_limit: Option<&config::RateLimitConfig>,
+        key_metadata: &HashMap<String, config::KeyMetadata>,
+    ) {
+        if keys.is_empty() {
+            return;
+        }
+
+        let reused = previous.and_then(|prev| {
+            if prev.key_sources.get(provider_name).map(|v| v.as_slice()) == Some(keys) {
+                prev.key_pools.get(provider_name).cloned()
             } else {
-                // Provider-level: expand to multiple models
-                let provider_name = &key;
-
-                // Create key pool for this provider
-                if !model_config.keys.is_empty() {
-                    key_pools.insert(
-                        provider_name.clone(),
-                        KeyPool::new(
-                            provider_name.clone(),
-                            model_config.keys.clone(),
-                            model_config.rotation_strategy.clone(),
-                        ),
-                    );
-                }
+                None
+            }
+        });
+        let pool = reused.unwrap_or_else(|| {
+            Arc::new(KeyPool::new(
+                provider_name.to_string(),
+                keys.to_vec(),
+                strategy.clone(),
+                rate_limit,
+                key_metadata,
+            ))
+        });
 
-                // If this provider is not in registry and has a base_url, create a custom provider entry
-                if !provider_registry.contains_key(provider_name) {
-                    if let Some(base_url) = &model_config.base_url {
-                        if !custom_providers.contains_key(provider_name) {
-                            custom_providers.insert(
-                                provider_name.clone(),
-                                ProviderConfig {
-                                    base_url: base_url.clone(),
-                                    api_key_env: None,
-                                    api_keys_env: None,
-                                    api_base_env: None,
-                                    models: model_config.models.clone(),
-                                    param_mappings: model_config.param_mappings.clone(),
-                                    headers: model_config.headers.clone(),
-                                    rate_limit: model_config.rate_limit.clone(),
-                                    special_handling: Default::default(),
-                                },
-                            );
-                        }
-                    }
-                }
+        key_sources.insert(provider_name.to_string(), keys.to_vec());
+        key_pools.insert(provider_name.to_string(), pool);
+    }
 
-                // Expand each model
-                for model_name in &model_config.models {
-                    let model_key = format!("{}/{}", provider_name, model_name);
-                    model_configs.insert(model_key, model_config.clone());
-                }
+    /// Re-expand the config this client was originally constructed from
+    /// (its file path if it has one, otherwise the default search paths)
+    /// and atomically swap it in.
+    pub fn reload(&self) -> Result<()> {
+        match &self.config_path {
+            Some(path) => self.reload_from_path(path),
+            None => self.reload_with(ConfigLoader::new()?),
+        }
+    }
+
+    /// Re-expand the config from a specific path and atomically swap it in.
+    pub fn reload_from_path(&self, path: &str) -> Result<()> {
+        self.reload_with(ConfigLoader::from_path(path)?)
+    }
+
+    fn reload_with(&self, loader: ConfigLoader) -> Result<()> {
+        let previous = self.state.read().clone();
+        let next = Self::expand_config(&loader, Some(&previous));
+        *self.state.write() = Arc::new(next);
+        Ok(())
+    }
+
+    /// Start a background thread that watches the config path (or, if none
+    /// was given, the default search paths) and calls `reload()` whenever
+    /// the underlying file changes. Dropping the returned handle stops the
+    /// watch.
+    pub fn watch(self: &Arc<Self>) -> Result<ReloadWatchHandle> {
+        use notify::Watcher;
+
+        let paths = match &self.config_path {
+            Some(path) => vec![std::path::PathBuf::from(path)],
+            None => ConfigLoader::get_config_paths(),
+        };
+
+        let (error_tx, _) = broadcast::channel(16);
+        let (event_tx, event_rx) = std::sync::mpsc::channel::<notify::Result<notify::Event>>();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+        })
+        .map_err(|e| LlmaoError::Config(format!("Failed to start config watcher: {}", e)))?;
+
+        for path in &paths {
+            if path.exists() {
+                let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
             }
         }
 
-        Ok(Self {
-            provider_registry,
-            custom_providers,
-            model_configs,
-            key_pools,
-            http_client: HttpClient::new()?,
+        let client = self.clone();
+        let errors_for_thread = error_tx.clone();
+        std::thread::spawn(move || {
+            for event in event_rx {
+                let Ok(event) = event else { continue };
+                if !matches!(event.kind, notify::EventKind::Modify(_) | notify::EventKind::Create(_)) {
+                    continue;
+                }
+                if let Err(e) = client.reload() {
+                    let _ = errors_for_thread.send(format!("Failed to reload config: {}", e));
+                }
+            }
+        });
+
+        Ok(ReloadWatchHandle {
+            _watcher: watcher,
+            errors: error_tx,
         })
     }
 
-    /// Get a provider configuration from registry or custom providers
-    fn get_provider(&self, name: &str) -> Result<&ProviderConfig> {
-        // First check built-in registry
-        if let Some(config) = self.provider_registry.get(name) {
-            return Ok(config);
-        }
-        // Then check custom providers (from user config with base_url)
-        self.custom_providers
+    /// Get a snapshot of the current config state. Cheap: only clones the `Arc`.
+    fn snapshot(&self) -> Arc<ClientState> {
+        self.state.read().clone()
+    }
+
+    /// Get a provider's configuration
+    fn get_provider(&self, name: &str) -> Result<ProviderConfig> {
+        self.snapshot()
+            .providers
             .get(name)
+            .cloned()
             .ok_or_else(|| LlmaoError::ProviderNotFound(name.to_string()))
     }
 
     /// Get the default model (first configured model)
     pub fn get_default_model(&self) -> Option<String> {
-        self.model_configs.keys().next().cloned()
+        self.snapshot().model_configs.keys().next().cloned()
     }
 
     /// Get all configured models
     pub fn get_configured_models(&self) -> Vec<String> {
-        self.model_configs.keys().cloned().collect()
+        self.snapshot().model_configs.keys().cloned().collect()
     }
 
-    /// Get an API key for a provider
-    fn get_api_key(&self, provider: &str) -> Result<String> {
-        if let Some(pool) = self.key_pools.get(provider) {
-            pool.get_key()
+    /// Get the configured proxy-server settings, if any
+    pub fn get_server_config(&self) -> Option<config::ServerConfig> {
+        self.snapshot().server.clone()
+    }
+
+    /// Get an API key usable for `route`'s model, scoped to its provider's
+    /// pool. `estimated_tokens`, if given, is charged against the chosen
+    /// key's token bucket so the pool doesn't hand out a key it already
+    /// knows can't afford the request.
+    fn get_api_key(&self, route: &ModelRoute, estimated_tokens: Option<u32>) -> Result<String> {
+        if let Some(pool) = self.snapshot().key_pools.get(&route.provider) {
+            pool.get_key_for(route, estimated_tokens)
                 .map(|k| k.value().to_string())
-                .ok_or_else(|| LlmaoError::NoKeysAvailable(provider.to_string()))
+                .ok_or_else(|| LlmaoError::NoKeysAvailable(route.provider.clone()))
         } else {
             // Try single env var from provider config
-            let config = self.get_provider(provider)?;
+            let config = self.get_provider(&route.provider)?;
             config
                 .get_api_keys()
                 .into_iter()
                 .next()
-                .ok_or_else(|| LlmaoError::NoKeysAvailable(provider.to_string()))
+                .ok_or_else(|| LlmaoError::NoKeysAvailable(route.provider.clone()))
         }
     }
 
-    /// Mark an API key as rate limited
-    fn mark_key_rate_limited(&self, provider: &str, key: &str, duration: std::time::Duration) {
-        if let Some(pool) = self.key_pools.get(provider) {
+    /// Mark an API key as rate limited. `duration` of `None` lets the pool
+    /// apply its own adaptive backoff for a bare rate-limit error that came
+    /// without a `retry-after`.
+    fn mark_key_rate_limited(&self, provider: &str, key: &str, duration: Option<std::time::Duration>) {
+        if let Some(pool) = self.snapshot().key_pools.get(provider) {
             pool.mark_rate_limited(key, duration);
         }
     }
 
-    /// Make a completion request
+    /// Record a successful request against `key`, resetting its adaptive
+    /// backoff counter so recovery after a rate limit isn't permanent.
+    fn mark_key_used(&self, provider: &str, key: &str) {
+        if let Some(pool) = self.snapshot().key_pools.get(provider) {
+            pool.record_usage(key);
+        }
+    }
+
+    /// Make a completion request. `model` may be a plain `provider/model`
+    /// identifier or an alias configured in `model_routes`, in which case
+    /// each listed target is tried in turn: a target is abandoned in favor
+    /// of the next on `RateLimited` (after exhausting that provider's own
+    /// key rotation) or a retriable HTTP error, and the failure from every
+    /// exhausted target is aggregated into the final error.
     pub async fn completion(
         &self,
         model: &str,
         request: CompletionRequest,
     ) -> Result<CompletionResponse> {
-        let route = ModelRoute::parse(model)?;
-        let provider_config = self.get_provider(&route.provider)?;
+        let routes = ModelRoute::parse_chain(model, &self.snapshot().model_routes)?;
+        let mut target_errors = Vec::new();
+
+        for route in &routes {
+            match self.completion_on_route(route, request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) if routes.len() > 1 && is_retriable_for_failover(&e) => {
+                    target_errors.push((route.to_string(), e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
-        // Build request body
-        let mut body = serde_json::to_value(&request)?;
+        Err(LlmaoError::AllTargetsFailed {
+            model: model.to_string(),
+            errors: target_errors,
+        })
+    }
 
-        // Set the actual model name
-        if let Some(obj) = body.as_object_mut() {
-            obj.insert(
-                "model".to_string(),
-                serde_json::Value::String(route.model_id()),
-            );
+    /// Run a completion request against a single resolved `route`, rotating
+    /// through that provider's key pool on rate limiting.
+    async fn completion_on_route(
+        &self,
+        route: &ModelRoute,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse> {
+        let provider_config = self.get_provider(&route.provider)?;
+        let backend = backend::backend_for(&provider_config);
+
+        if request_needs_tool_support(&request) && !backend.supports_tools() {
+            return Err(LlmaoError::Config(format!(
+                "provider '{}' does not support tool calling, so tools/tool_choice would be silently dropped",
+                route.provider
+            )));
         }
 
-        // Apply parameter mappings
-        provider_config.apply_param_mappings(&mut body);
+        // Set the actual model name, then let the backend translate the
+        // request into this provider's wire format
+        let mut request = request;
+        request.model = route.model_id();
+
+        // Estimate prompt size up front so both key selection and the
+        // proactive rate-limit gate can account for the token bucket, not
+        // just the request-count window.
+        let estimated_tokens =
+            tokenizer::count_tokens_with_encoding(&request, provider_config.get_encoding()) as u32;
+
+        let body = backend.build_body(&request, &provider_config);
 
         // Build URL
         let base_url = provider_config.get_base_url();
-        let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+        let url = backend.build_url(&base_url, &request.model);
 
         // Build extra headers
         let extra_headers = if provider_config.headers.is_empty() {
@@ -251,6 +420,7 @@ impl LlmClient {
 
         // Try with key rotation on rate limit
         let max_attempts = self
+            .snapshot()
             .key_pools
             .get(&route.provider)
             .map(|p| p.len())
@@ -258,68 +428,90 @@ impl LlmClient {
         let mut last_error = None;
 
         for _ in 0..max_attempts {
-            let api_key = self.get_api_key(&route.provider)?;
+            let api_key = self.get_api_key(route, Some(estimated_tokens))?;
 
             match self
                 .http_client
-                .post_with_retry::<_, CompletionResponse>(
+                .post_with_retry::<_, serde_json::Value>(
                     &url,
                     &body,
                     &api_key,
                     extra_headers.as_ref(),
                     &route.provider,
                     3,
+                    Some(estimated_tokens),
                 )
                 .await
             {
-                Ok(response) => return Ok(response),
-                Err(LlmaoError::RateLimited { retry_after, .. }) => {
-                    // Mark this key as rate limited and try next
-                    let duration = retry_after
-                        .map(std::time::Duration::from_secs)
-                        .unwrap_or(std::time::Duration::from_secs(60));
+                Ok(raw) => {
+                    self.mark_key_used(&route.provider, &api_key);
+                    return backend.parse_response(raw);
+                }
+                Err(LlmaoError::RateLimited {
+                    retry_after,
+                    retry_info,
+                    ..
+                }) => {
+                    // Mark this key as rate limited and try next. Without an
+                    // explicit `retry-after` the pool falls back to adaptive
+                    // backoff rather than a fixed window.
+                    let duration = retry_after.map(std::time::Duration::from_secs);
                     self.mark_key_rate_limited(&route.provider, &api_key, duration);
                     last_error = Some(LlmaoError::RateLimited {
                         provider: route.provider.clone(),
                         retry_after,
+                        retry_info,
                     });
                 }
                 Err(e) => return Err(e),
             }
         }
 
-        Err(last_error.unwrap_or(LlmaoError::NoKeysAvailable(route.provider)))
+        Err(last_error.unwrap_or(LlmaoError::NoKeysAvailable(route.provider.clone())))
     }
 
-    /// Make a streaming completion request
-    /// Returns a vector of chunks (for Python compatibility - we collect all chunks in a blocking call,
-    /// then Python iterates over them. For true streaming, we'd need async Python support.)
+    /// Make a streaming completion request, pushing each parsed chunk into
+    /// `tx` as soon as it arrives rather than collecting the whole
+    /// response first. Intended to run inside a spawned task so the
+    /// receiving end sees real time-to-first-token latency; the channel
+    /// closes (by `tx` being dropped) once the stream ends or errors,
+    /// which is what signals the receiver to stop.
     pub async fn completion_stream(
         &self,
         model: &str,
         request: CompletionRequest,
-    ) -> Result<Vec<api::StreamChunk>> {
+        tx: mpsc::UnboundedSender<Result<api::StreamChunk>>,
+    ) -> Result<()> {
         use futures::StreamExt;
 
         let route = ModelRoute::parse(model)?;
         let provider_config = self.get_provider(&route.provider)?;
+        let backend = backend::backend_for(&provider_config);
 
-        // Build request body with stream=true
-        let mut body = serde_json::to_value(&request)?;
-        if let Some(obj) = body.as_object_mut() {
-            obj.insert(
-                "model".to_string(),
-                serde_json::Value::String(route.model_id()),
-            );
-            obj.insert("stream".to_string(), serde_json::Value::Bool(true));
+        if request_needs_tool_support(&request) && !backend.supports_tools() {
+            return Err(LlmaoError::Config(format!(
+                "provider '{}' does not support tool calling, so tools/tool_choice would be silently dropped",
+                route.provider
+            )));
         }
 
-        // Apply parameter mappings
-        provider_config.apply_param_mappings(&mut body);
+        // Set the actual model name and force streaming, then let the
+        // backend translate the request into this provider's wire format
+        let mut request = request;
+        request.model = route.model_id();
+        request.stream = Some(true);
+
+        // Estimate prompt size up front so both key selection and the
+        // proactive rate-limit gate can account for the token bucket, not
+        // just the request-count window.
+        let estimated_tokens =
+            tokenizer::count_tokens_with_encoding(&request, provider_config.get_encoding()) as u32;
+
+        let body = backend.build_body(&request, &provider_config);
 
         // Build URL
         let base_url = provider_config.get_base_url();
-        let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+        let url = backend.build_url(&base_url, &request.model);
 
         // Build extra headers
         let extra_headers = if provider_config.headers.is_empty() {
@@ -338,57 +530,158 @@ impl LlmClient {
         };
 
         // Get API key
-        let api_key = self.get_api_key(&route.provider)?;
+        let api_key = self.get_api_key(route, Some(estimated_tokens))?;
 
-        // Make streaming request
+        // Make streaming request, transparently reconnecting (replaying
+        // with `Last-Event-ID`) if the connection drops mid-stream instead
+        // of ending the iterator on the first transport hiccup.
         let mut stream = self
             .http_client
-            .post_stream(&url, &body, &api_key, extra_headers.as_ref(), &route.provider)
+            .post_stream_resilient(
+                &url,
+                &body,
+                &api_key,
+                extra_headers.as_ref(),
+                &route.provider,
+                backend,
+                Some(estimated_tokens),
+            )
             .await?;
 
-        // Collect chunks
-        let mut chunks = Vec::new();
-        let mut buffer = String::new();
-
         while let Some(result) = stream.next().await {
-            let bytes = result?;
-            buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-            // Process complete lines
-            while let Some(newline_pos) = buffer.find('\n') {
-                let line = buffer[..newline_pos].to_string();
-                buffer = buffer[newline_pos + 1..].to_string();
-
-                if let Some(chunk) = api::parse_sse_line(&line)? {
-                    chunks.push(chunk);
-                }
+            let is_err = result.is_err();
+            if tx.send(result).is_err() {
+                // Receiver gone (iterator dropped); stop early
+                return Ok(());
+            }
+            if is_err {
+                return Ok(());
             }
         }
 
-        // Process remaining buffer
-        if !buffer.trim().is_empty() {
-            if let Some(chunk) = api::parse_sse_line(&buffer)? {
-                chunks.push(chunk);
-            }
+        Ok(())
+    }
+
+    /// Drive a multi-step tool-calling conversation to completion: send
+    /// `request`, run any tool calls the model asks for through `executor`,
+    /// append the results as `role: "tool"` messages, and resend until the
+    /// model returns a normal message or `executor`'s step limit is hit.
+    /// Returns the final response alongside the full assistant/tool
+    /// message trace. Errors if `request.tools` is set but the resolved
+    /// provider's backend doesn't advertise tool support.
+    pub async fn completion_with_tools(
+        &self,
+        model: &str,
+        request: CompletionRequest,
+        executor: &tools::ToolExecutor,
+    ) -> Result<(CompletionResponse, Vec<Message>)> {
+        let route = ModelRoute::parse(model)?;
+        let provider_config = self.get_provider(&route.provider)?;
+
+        if request.tools.is_some() && !backend::backend_for(&provider_config).supports_tools() {
+            return Err(LlmaoError::Config(format!(
+                "provider '{}' does not support tool calling",
+                route.provider
+            )));
         }
 
-        Ok(chunks)
+        executor
+            .run_with_trace(request, |req| self.completion(model, req))
+            .await
     }
 
     /// List available providers
     pub fn providers(&self) -> Vec<String> {
-        self.provider_registry.keys().cloned().collect()
+        self.snapshot().providers.keys().cloned().collect()
     }
 
     /// Get provider info
     pub fn provider_info(&self, name: &str) -> Option<ProviderInfo> {
-        self.provider_registry.get(name).map(|p| ProviderInfo {
+        let state = self.snapshot();
+        state.providers.get(name).map(|p| ProviderInfo {
             name: name.to_string(),
             base_url: p.base_url.clone(),
             models: p.models.clone(),
-            has_keys: self.key_pools.contains_key(name),
+            has_keys: state.key_pools.contains_key(name),
         })
     }
+
+    /// Render every provider's key pool as Prometheus text exposition
+    /// format, for scraping pool health (request counts, rate-limit state,
+    /// wait times).
+    pub fn pool_metrics(&self) -> String {
+        let state = self.snapshot();
+        let registry = router::PoolRegistry::new(state.key_pools.iter().map(|(name, pool)| (name.as_str(), pool.as_ref())));
+        registry.render_metrics()
+    }
+}
+
+/// Whether a completion failure should move on to the next target in a
+/// failover chain rather than being returned immediately. Rate limiting
+/// (once the target's own key pool is exhausted) and connection-level
+/// failures are worth retrying elsewhere; auth, config, and parsing errors
+/// are specific to the request or key and would just fail again.
+fn is_retriable_for_failover(error: &LlmaoError) -> bool {
+    matches!(
+        error,
+        LlmaoError::RateLimited { .. } | LlmaoError::Request(_) | LlmaoError::Timeout(_)
+    )
+}
+
+/// Whether `request` carries anything a tool-incapable backend would
+/// silently drop. `tools` alone (letting the model decide whether to call
+/// one) is the common case and must gate just as `tool_choice` does --
+/// checking `tool_choice` alone would let tool schemas through to a backend
+/// that never reads `request.tools` at all.
+fn request_needs_tool_support(request: &CompletionRequest) -> bool {
+    request.tools.is_some() || request.tool_choice.is_some()
+}
+
+#[cfg(test)]
+mod tool_gate_tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_alone_requires_tool_support() {
+        let mut request = CompletionRequest::new("test-model".to_string(), vec![]);
+        request.tools = Some(vec![]);
+
+        assert!(request_needs_tool_support(&request));
+    }
+
+    #[test]
+    fn test_tool_choice_alone_requires_tool_support() {
+        let mut request = CompletionRequest::new("test-model".to_string(), vec![]);
+        request.tool_choice = Some(ToolChoice::Mode("auto".to_string()));
+
+        assert!(request_needs_tool_support(&request));
+    }
+
+    #[test]
+    fn test_neither_tools_nor_tool_choice_does_not_require_tool_support() {
+        let request = CompletionRequest::new("test-model".to_string(), vec![]);
+
+        assert!(!request_needs_tool_support(&request));
+    }
+}
+
+/// Handle to a running config watch on an `LlmClient`. Dropping it stops
+/// the underlying watcher.
+pub struct ReloadWatchHandle {
+    /// Kept alive for as long as the watch should run
+    _watcher: notify::RecommendedWatcher,
+
+    /// Broadcasts a message whenever a reload fails
+    errors: broadcast::Sender<String>,
+}
+
+impl ReloadWatchHandle {
+    /// Subscribe to reload errors (e.g. malformed config files). When a
+    /// reload fails the last-known-good state keeps serving and the error
+    /// is broadcast here instead of poisoning shared state.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<String> {
+        self.errors.subscribe()
+    }
 }
 
 /// Provider information
@@ -427,7 +720,7 @@ impl PyLlmClient {
                 .map_err(|e| LlmaoError::Config(format!("Invalid config dict: {}", e)))?;
 
             let loader = ConfigLoader::from_config(providers_config)?;
-            LlmClient::from_loader(loader)?
+            LlmClient::from_loader(loader, None)?
         } else if let Some(path) = config_path {
             LlmClient::with_config_path(path)?
         } else {
@@ -443,9 +736,12 @@ impl PyLlmClient {
         })
     }
 
-    /// Make a completion request
+    /// Make a completion request. `tool_choice` accepts `"auto"`, `"none"`,
+    /// `"required"`, or a dict naming a specific function
+    /// (`{"type": "function", "function": {"name": ...}}`) to constrain or
+    /// disable tool calling for this turn.
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, stream=None, **kwargs))]
+    #[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, stream=None, tool_choice=None, **kwargs))]
     fn completion(
         &self,
         py: Python<'_>,
@@ -454,6 +750,7 @@ impl PyLlmClient {
         temperature: Option<f32>,
         max_tokens: Option<u32>,
         stream: Option<bool>,
+        tool_choice: Option<&Bound<'_, PyAny>>,
         kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
         // Resolve model: use provided or get default from config
@@ -480,6 +777,9 @@ impl PyLlmClient {
         if let Some(s) = stream {
             request.stream = Some(s);
         }
+        if let Some(choice) = tool_choice {
+            request.tool_choice = Some(convert_tool_choice(choice)?);
+        }
 
         // Add extra kwargs
         if let Some(extra) = kwargs {
@@ -497,73 +797,271 @@ impl PyLlmClient {
             .runtime
             .block_on(async move { client.completion(&model_str, request).await })?;
 
-        // Convert response to Python dict
-        let dict = PyDict::new(py);
-        dict.set_item("id", &response.id)?;
-        dict.set_item("object", &response.object)?;
-        dict.set_item("created", response.created)?;
-        dict.set_item("model", &response.model)?;
-
-        // Convert choices
-        let choices = PyList::empty(py);
-        for choice in &response.choices {
-            let choice_dict = PyDict::new(py);
-            choice_dict.set_item("index", choice.index)?;
-            choice_dict.set_item("finish_reason", &choice.finish_reason)?;
-
-            let message_dict = PyDict::new(py);
-            message_dict.set_item("role", &choice.message.role)?;
-
-            // Use content, or fall back to reasoning if content is empty
-            let content = {
-                let main_content = choice.message.content.to_string_content();
-                if main_content.is_empty() {
-                    choice.message.reasoning.clone().unwrap_or_default()
-                } else {
-                    main_content
-                }
-            };
-            message_dict.set_item("content", content)?;
+        let dict = completion_response_to_pydict(py, &response)?;
+        Ok(dict.into())
+    }
+
+    /// Count the tokens `messages` would cost for `model`, using that
+    /// provider's configured tokenizer encoding (falling back to a
+    /// cl100k-style default if unset or unrecognized). Useful for checking
+    /// a prompt against a budget before sending it.
+    #[pyo3(signature = (messages, model=None))]
+    fn count_tokens(&self, messages: &Bound<'_, PyList>, model: Option<&str>) -> PyResult<usize> {
+        let model_str = if let Some(m) = model {
+            m.to_string()
+        } else {
+            self.inner.get_default_model().ok_or_else(|| {
+                LlmaoError::Config("No model specified and no models configured. Either pass model parameter or add models to config.".to_string())
+            })?
+        };
+
+        let rust_messages = convert_messages(messages)?;
+        let request = CompletionRequest::new(model_str.clone(), rust_messages);
+
+        let route = ModelRoute::parse(&model_str)?;
+        let encoding = self.inner.get_provider(&route.provider).map(|p| p.get_encoding()).unwrap_or_default();
+
+        Ok(tokenizer::count_tokens_with_encoding(&request, encoding))
+    }
+
+    /// Trim `messages` down to `max_prompt_tokens`, dropping the oldest
+    /// non-system messages first and always preserving the most recent
+    /// user turn, using `model`'s configured tokenizer encoding (falling
+    /// back to a cl100k-style default if unset or unrecognized). Lets
+    /// callers fit a prompt to a context window and avoid provider-side
+    /// 400s before ever sending the request.
+    #[pyo3(signature = (messages, max_prompt_tokens, model=None))]
+    fn truncate_to_budget(
+        &self,
+        py: Python<'_>,
+        messages: &Bound<'_, PyList>,
+        max_prompt_tokens: usize,
+        model: Option<&str>,
+    ) -> PyResult<Py<PyList>> {
+        let model_str = if let Some(m) = model {
+            m.to_string()
+        } else {
+            self.inner.get_default_model().ok_or_else(|| {
+                LlmaoError::Config("No model specified and no models configured. Either pass model parameter or add models to config.".to_string())
+            })?
+        };
+
+        let rust_messages = convert_messages(messages)?;
+        let mut request = CompletionRequest::new(model_str.clone(), rust_messages);
+
+        let route = ModelRoute::parse(&model_str)?;
+        let encoding = self.inner.get_provider(&route.provider).map(|p| p.get_encoding()).unwrap_or_default();
 
-            // Also expose reasoning if present
-            if let Some(reasoning) = &choice.message.reasoning {
-                message_dict.set_item("reasoning", reasoning)?;
+        request.truncate_to_budget_with_encoding(max_prompt_tokens, encoding);
+
+        let truncated = PyList::empty(py);
+        for message in &request.messages {
+            truncated.append(message_to_pydict(py, message)?)?;
+        }
+        Ok(truncated.into())
+    }
+
+    /// Run a multi-step tool-calling conversation. `tools` is a list of
+    /// OpenAI-style tool schema dicts; `callbacks` maps each tool's
+    /// function name to a Python callable invoked with the parsed JSON
+    /// arguments and expected to return a string (or something
+    /// `str()`-able). Returns `(response, trace)`, where `trace` is the
+    /// full list of assistant/tool messages appended while driving the
+    /// loop, including the final assistant answer.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (messages, tools, callbacks, model=None, max_steps=10, temperature=None, max_tokens=None, **kwargs))]
+    fn completion_with_tools(
+        &self,
+        py: Python<'_>,
+        messages: &Bound<'_, PyList>,
+        tools: &Bound<'_, PyList>,
+        callbacks: &Bound<'_, PyDict>,
+        model: Option<&str>,
+        max_steps: usize,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        let model_str = if let Some(m) = model {
+            m.to_string()
+        } else {
+            self.inner.get_default_model().ok_or_else(|| {
+                LlmaoError::Config("No model specified and no models configured. Either pass model parameter or add models to config.".to_string())
+            })?
+        };
+
+        let rust_messages = convert_messages(messages)?;
+        let rust_tools = convert_tools(tools)?;
+
+        let mut request = CompletionRequest::new(model_str.clone(), rust_messages);
+        request.tools = Some(rust_tools);
+
+        if let Some(temp) = temperature {
+            request.temperature = Some(temp);
+        }
+        if let Some(max) = max_tokens {
+            request.max_tokens = Some(max);
+        }
+        if let Some(extra) = kwargs {
+            for (key, value) in extra.iter() {
+                let key_str: String = key.extract()?;
+                let json_value = python_to_json(&value)?;
+                request.extra.insert(key_str, json_value);
             }
+        }
+
+        let mut executor = tools::ToolExecutor::new(max_steps);
+        for (name, callback) in callbacks.iter() {
+            let name_str: String = name.extract()?;
+            let callback: Py<PyAny> = callback.unbind();
+            let handler: tools::ToolHandler = Arc::new(move |args: serde_json::Value| {
+                let callback = callback.clone();
+                Box::pin(async move {
+                    Python::with_gil(|py| {
+                        let py_args = json_to_python(py, &args)?;
+                        let result = callback
+                            .bind(py)
+                            .call1((py_args,))
+                            .map_err(|e| LlmaoError::Internal(format!("tool callback failed: {}", e)))?;
+                        match result.extract::<String>() {
+                            Ok(s) => Ok(s),
+                            Err(_) => Ok(result
+                                .str()
+                                .map_err(|e| LlmaoError::Internal(e.to_string()))?
+                                .to_string()),
+                        }
+                    })
+                })
+            });
+            executor.register(name_str, handler);
+        }
 
-            // Also expose tool_calls if present
-            if let Some(tool_calls) = &choice.message.tool_calls {
-                let tools_list = PyList::empty(py);
-                for tool in tool_calls {
-                    let tool_dict = PyDict::new(py);
-                    tool_dict.set_item("id", &tool.id)?;
-                    tool_dict.set_item("type", &tool.call_type)?;
+        let client = self.inner.clone();
+        let (response, trace) = self.runtime.block_on(async move {
+            client.completion_with_tools(&model_str, request, &executor).await
+        })?;
 
-                    let func_dict = PyDict::new(py);
-                    func_dict.set_item("name", &tool.function.name)?;
-                    func_dict.set_item("arguments", &tool.function.arguments)?;
+        let response_dict = completion_response_to_pydict(py, &response)?;
 
-                    tool_dict.set_item("function", func_dict)?;
-                    tools_list.append(tool_dict)?;
-                }
-                message_dict.set_item("tool_calls", tools_list)?;
+        let trace_list = PyList::empty(py);
+        for message in &trace {
+            trace_list.append(message_to_pydict(py, message)?)?;
+        }
+
+        let result = PyDict::new(py);
+        result.set_item("response", response_dict)?;
+        result.set_item("trace", trace_list)?;
+        Ok(result.into())
+    }
+
+    /// Run a multi-step tool-calling conversation from a single combined
+    /// registry instead of separate `tools`/`callbacks` arguments: `tools`
+    /// maps each function name to a `(callable, schema)` pair, where
+    /// `schema` is the same OpenAI-style tool dict `completion_with_tools`
+    /// expects (`{"type": "function", "function": {...}}`) and `callable`
+    /// is invoked with the parsed JSON arguments and expected to return a
+    /// string (or something `str()`-able). Returns `(response, trace)`,
+    /// where `trace` is the full list of assistant/tool messages appended
+    /// while driving the loop, including the final assistant answer.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (messages, tools, model=None, max_steps=10, temperature=None, max_tokens=None, **kwargs))]
+    fn run_tools(
+        &self,
+        py: Python<'_>,
+        messages: &Bound<'_, PyList>,
+        tools: &Bound<'_, PyDict>,
+        model: Option<&str>,
+        max_steps: usize,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        let model_str = if let Some(m) = model {
+            m.to_string()
+        } else {
+            self.inner.get_default_model().ok_or_else(|| {
+                LlmaoError::Config("No model specified and no models configured. Either pass model parameter or add models to config.".to_string())
+            })?
+        };
+
+        let rust_messages = convert_messages(messages)?;
+
+        let schemas = PyList::empty(py);
+        let mut executor = tools::ToolExecutor::new(max_steps);
+
+        for (name, value) in tools.iter() {
+            let name_str: String = name.extract()?;
+            let pair: &Bound<'_, PyTuple> = value.cast().map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                    "tools['{}'] must be a (callable, schema) tuple",
+                    name_str
+                ))
+            })?;
+            if pair.len() != 2 {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "tools['{}'] must be a 2-element (callable, schema) tuple",
+                    name_str
+                )));
             }
+            let callback: Py<PyAny> = pair.get_item(0)?.unbind();
+            schemas.append(pair.get_item(1)?)?;
+
+            let handler: tools::ToolHandler = Arc::new(move |args: serde_json::Value| {
+                let callback = callback.clone();
+                Box::pin(async move {
+                    Python::with_gil(|py| {
+                        let py_args = json_to_python(py, &args)?;
+                        let result = callback
+                            .bind(py)
+                            .call1((py_args,))
+                            .map_err(|e| LlmaoError::Internal(format!("tool callback failed: {}", e)))?;
+                        match result.extract::<String>() {
+                            Ok(s) => Ok(s),
+                            Err(_) => Ok(result
+                                .str()
+                                .map_err(|e| LlmaoError::Internal(e.to_string()))?
+                                .to_string()),
+                        }
+                    })
+                })
+            });
+            executor.register(name_str, handler);
+        }
 
-            choice_dict.set_item("message", message_dict)?;
+        let rust_tools = convert_tools(&schemas)?;
+        let mut request = CompletionRequest::new(model_str.clone(), rust_messages);
+        request.tools = Some(rust_tools);
 
-            choices.append(choice_dict)?;
+        if let Some(temp) = temperature {
+            request.temperature = Some(temp);
         }
-        dict.set_item("choices", choices)?;
+        if let Some(max) = max_tokens {
+            request.max_tokens = Some(max);
+        }
+        if let Some(extra) = kwargs {
+            for (key, value) in extra.iter() {
+                let key_str: String = key.extract()?;
+                let json_value = python_to_json(&value)?;
+                request.extra.insert(key_str, json_value);
+            }
+        }
+
+        let client = self.inner.clone();
+        let (response, trace) = self.runtime.block_on(async move {
+            client.completion_with_tools(&model_str, request, &executor).await
+        })?;
+
+        let response_dict = completion_response_to_pydict(py, &response)?;
 
-        // Convert usage
-        if let Some(usage) = &response.usage {
-            let usage_dict = PyDict::new(py);
-            usage_dict.set_item("prompt_tokens", usage.prompt_tokens)?;
-            usage_dict.set_item("completion_tokens", usage.completion_tokens)?;
-            usage_dict.set_item("total_tokens", usage.total_tokens)?;
-            dict.set_item("usage", usage_dict)?;
+        let trace_list = PyList::empty(py);
+        for message in &trace {
+            trace_list.append(message_to_pydict(py, message)?)?;
         }
 
-        Ok(dict.into())
+        let result = PyDict::new(py);
+        result.set_item("response", response_dict)?;
+        result.set_item("trace", trace_list)?;
+        Ok(result.into())
     }
 
     /// List available providers
@@ -591,9 +1089,47 @@ impl PyLlmClient {
         }
     }
 
-    /// Stream a completion request, yielding chunks as they arrive
+    /// Re-read the config this client was constructed from (or, if given, a
+    /// different `path`) and atomically apply the changes without rebuilding
+    /// the client or losing live rate-limit state for unchanged providers.
+    #[pyo3(signature = (path=None))]
+    fn reload(&self, path: Option<&str>) -> PyResult<()> {
+        match path {
+            Some(path) => self.inner.reload_from_path(path)?,
+            None => self.inner.reload()?,
+        }
+        Ok(())
+    }
+
+    /// Bind a local socket and serve an OpenAI-compatible
+    /// `/v1/chat/completions` endpoint, forwarding every request through
+    /// this client's existing routing, key-pool rotation, and
+    /// retry/backoff. `host`/`port` override the `server` section of the
+    /// config if set, falling back to `127.0.0.1:8080`. Blocks the calling
+    /// thread until the server errors or the process is killed, so most
+    /// callers run it from a dedicated thread.
+    #[pyo3(signature = (host=None, port=None))]
+    fn serve(&self, host: Option<&str>, port: Option<u16>) -> PyResult<()> {
+        let configured = self.inner.get_server_config().unwrap_or_default();
+        let server_config = config::ServerConfig {
+            host: host.map(str::to_string).unwrap_or(configured.host),
+            port: port.unwrap_or(configured.port),
+            log_completions: configured.log_completions,
+        };
+
+        let client = self.inner.clone();
+        self.runtime
+            .block_on(async move { server::serve(client, &server_config).await })?;
+        Ok(())
+    }
+
+    /// Stream a completion request, yielding chunks as they arrive. By
+    /// default tool-call deltas are yielded raw, one partial fragment per
+    /// chunk; pass `aggregate_tool_calls=True` to have the iterator buffer
+    /// them instead and yield one fully-assembled `tool_calls` entry per
+    /// completed call.
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, **kwargs))]
+    #[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, aggregate_tool_calls=None, **kwargs))]
     fn stream_completion(
         &self,
         py: Python<'_>,
@@ -601,6 +1137,7 @@ impl PyLlmClient {
         model: Option<&str>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+        aggregate_tool_calls: Option<bool>,
         kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<StreamIterator>> {
         // Resolve model
@@ -634,27 +1171,88 @@ impl PyLlmClient {
             }
         }
 
-        // Run streaming completion synchronously
+        // Spawn the streaming request on our runtime and have the
+        // iterator pull parsed chunks off a channel as they arrive,
+        // instead of blocking here until the whole response is in.
         let client = self.inner.clone();
-        let chunks = self
-            .runtime
-            .block_on(async move { client.completion_stream(&model_str, request).await })?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let err_tx = tx.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = client.completion_stream(&model_str, request, tx).await {
+                let _ = err_tx.send(Err(e));
+            }
+        });
 
-        // Create iterator with collected chunks
-        Py::new(py, StreamIterator::new(chunks))
+        Py::new(
+            py,
+            StreamIterator::new(
+                rx,
+                self.runtime.handle().clone(),
+                aggregate_tool_calls.unwrap_or(false),
+            ),
+        )
     }
 }
 
-/// Python iterator for streaming chunks
+/// Python iterator for streaming chunks. Pulls parsed chunks off a channel
+/// fed by a background task on `handle`, blocking only until the next
+/// chunk (or the end of the stream) is available, rather than walking a
+/// pre-collected vector.
+///
+/// When `aggregate` is set, raw tool-call deltas are withheld from
+/// `__next__`'s output and instead fed into `pending_call`, keyed by the
+/// delta's `index`; a change of index (a new call has started) or the end
+/// of the stream finalizes the buffered call and queues it in `ready` to
+/// be handed back on the next `__next__` call. Content deltas are
+/// unaffected and still surface immediately.
 #[pyclass]
 struct StreamIterator {
-    chunks: Vec<api::StreamChunk>,
-    index: usize,
+    rx: mpsc::UnboundedReceiver<Result<api::StreamChunk>>,
+    handle: tokio::runtime::Handle,
+    aggregate: bool,
+    pending_call: Option<(u32, api::streaming::ToolCallAccumulator)>,
+    ready: std::collections::VecDeque<Py<PyDict>>,
 }
 
 impl StreamIterator {
-    fn new(chunks: Vec<api::StreamChunk>) -> Self {
-        Self { chunks, index: 0 }
+    fn new(
+        rx: mpsc::UnboundedReceiver<Result<api::StreamChunk>>,
+        handle: tokio::runtime::Handle,
+        aggregate: bool,
+    ) -> Self {
+        Self {
+            rx,
+            handle,
+            aggregate,
+            pending_call: None,
+            ready: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Finalize the currently buffered tool call, if any, and append its
+    /// completed representation to `ready`.
+    fn flush_pending_call(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some((index, acc)) = self.pending_call.take() {
+            let completed = acc.finalize(index)?;
+
+            let tc_dict = PyDict::new(py);
+            tc_dict.set_item("index", index)?;
+            tc_dict.set_item("id", &completed.id)?;
+            tc_dict.set_item("type", &completed.call_type)?;
+
+            let func_dict = PyDict::new(py);
+            func_dict.set_item("name", &completed.name)?;
+            func_dict.set_item("arguments", &completed.arguments)?;
+            tc_dict.set_item("function", func_dict)?;
+
+            let tc_list = PyList::empty(py);
+            tc_list.append(tc_dict)?;
+
+            let dict = PyDict::new(py);
+            dict.set_item("tool_calls", tc_list)?;
+            self.ready.push_back(dict.into());
+        }
+        Ok(())
     }
 }
 
@@ -664,61 +1262,98 @@ impl StreamIterator {
         slf
     }
 
-    fn __next__(&mut self, py: Python<'_>) -> Option<Py<PyDict>> {
-        if self.index >= self.chunks.len() {
-            return None;
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Py<PyDict>>> {
+        if let Some(ready) = self.ready.pop_front() {
+            return Ok(Some(ready));
         }
 
-        let chunk = &self.chunks[self.index];
-        self.index += 1;
+        let chunk = match self.handle.block_on(self.rx.recv()) {
+            None => {
+                if self.aggregate {
+                    self.flush_pending_call(py)?;
+                }
+                return Ok(self.ready.pop_front());
+            }
+            Some(Err(e)) => return Err(e.into()),
+            Some(Ok(chunk)) => chunk,
+        };
 
         let dict = PyDict::new(py);
-        dict.set_item("id", &chunk.id).ok()?;
-        dict.set_item("model", &chunk.model).ok()?;
-        dict.set_item("created", chunk.created).ok()?;
+        dict.set_item("id", &chunk.id)?;
+        dict.set_item("model", &chunk.model)?;
+        dict.set_item("created", chunk.created)?;
 
         // Extract content from first choice delta
         if let Some(choice) = chunk.choices.first() {
             if let Some(content) = &choice.delta.content {
-                dict.set_item("content", content).ok()?;
+                dict.set_item("content", content)?;
             }
             if let Some(role) = &choice.delta.role {
-                dict.set_item("role", role).ok()?;
+                dict.set_item("role", role)?;
             }
             if let Some(reason) = &choice.finish_reason {
-                dict.set_item("finish_reason", reason).ok()?;
+                dict.set_item("finish_reason", reason)?;
             }
-            dict.set_item("index", choice.index).ok()?;
+            dict.set_item("index", choice.index)?;
 
-            // Include tool call deltas if present
             if let Some(tool_calls) = &choice.delta.tool_calls {
-                let tc_list = PyList::empty(py);
-                for tc in tool_calls {
-                    let tc_dict = PyDict::new(py);
-                    tc_dict.set_item("index", tc.index).ok()?;
-                    if let Some(id) = &tc.id {
-                        tc_dict.set_item("id", id).ok()?;
-                    }
-                    if let Some(t) = &tc.call_type {
-                        tc_dict.set_item("type", t).ok()?;
+                if self.aggregate {
+                    for tc in tool_calls {
+                        if self.pending_call.as_ref().is_some_and(|(idx, _)| *idx != tc.index) {
+                            self.flush_pending_call(py)?;
+                        }
+                        let (_, acc) = self
+                            .pending_call
+                            .get_or_insert_with(|| (tc.index, api::streaming::ToolCallAccumulator::default()));
+                        if let Some(id) = &tc.id {
+                            acc.id = id.clone();
+                        }
+                        if let Some(t) = &tc.call_type {
+                            acc.call_type = t.clone();
+                        }
+                        if let Some(func) = &tc.function {
+                            if let Some(name) = &func.name {
+                                acc.name.push_str(name);
+                            }
+                            if let Some(args) = &func.arguments {
+                                acc.arguments.push_str(args);
+                            }
+                        }
                     }
-                    if let Some(func) = &tc.function {
-                        let func_dict = PyDict::new(py);
-                        if let Some(name) = &func.name {
-                            func_dict.set_item("name", name).ok()?;
+                } else {
+                    let tc_list = PyList::empty(py);
+                    for tc in tool_calls {
+                        let tc_dict = PyDict::new(py);
+                        tc_dict.set_item("index", tc.index)?;
+                        if let Some(id) = &tc.id {
+                            tc_dict.set_item("id", id)?;
                         }
-                        if let Some(args) = &func.arguments {
-                            func_dict.set_item("arguments", args).ok()?;
+                        if let Some(t) = &tc.call_type {
+                            tc_dict.set_item("type", t)?;
                         }
-                        tc_dict.set_item("function", func_dict).ok()?;
+                        if let Some(func) = &tc.function {
+                            let func_dict = PyDict::new(py);
+                            if let Some(name) = &func.name {
+                                func_dict.set_item("name", name)?;
+                            }
+                            if let Some(args) = &func.arguments {
+                                func_dict.set_item("arguments", args)?;
+                            }
+                            tc_dict.set_item("function", func_dict)?;
+                        }
+                        tc_list.append(tc_dict)?;
                     }
-                    tc_list.append(tc_dict).ok()?;
+                    dict.set_item("tool_calls", tc_list)?;
                 }
-                dict.set_item("tool_calls", tc_list).ok()?;
+            }
+
+            if self.aggregate && choice.finish_reason.is_some() {
+                self.flush_pending_call(py)?;
             }
         }
 
-        Some(dict.into())
+        self.ready.push_back(dict.into());
+        Ok(self.ready.pop_front())
     }
 }
 
@@ -741,8 +1376,9 @@ fn convert_messages(messages: &Bound<'_, PyList>) -> PyResult<Vec<Message>> {
                 MessageContent::Text(String::new())
             } else if let Ok(s) = content_item.extract::<String>() {
                 MessageContent::Text(s)
+            } else if let Ok(list) = content_item.cast::<PyList>() {
+                MessageContent::Parts(convert_content_parts(list)?)
             } else {
-                // TODO: Handle content arrays for multimodal
                 MessageContent::Text(content_item.str()?.to_string())
             }
         } else {
@@ -791,6 +1427,7 @@ fn convert_messages(messages: &Bound<'_, PyList>) -> PyResult<Vec<Message>> {
                                         id,
                                         call_type,
                                         function: FunctionCall { name, arguments },
+                                        complete: None,
                                     });
                                 }
                             }
@@ -811,7 +1448,6 @@ fn convert_messages(messages: &Bound<'_, PyList>) -> PyResult<Vec<Message>> {
         result.push(Message {
             role,
             content,
-            reasoning: None,
             name,
             tool_calls,
             tool_call_id,
@@ -821,6 +1457,200 @@ fn convert_messages(messages: &Bound<'_, PyList>) -> PyResult<Vec<Message>> {
     Ok(result)
 }
 
+/// Convert a Python list of OpenAI-style content-part dicts (`{"type": "text",
+/// "text": ...}` or `{"type": "image_url", "image_url": {"url": ..., "detail": ...}}`)
+/// into `ContentPart`s, for multimodal messages sent as a content array
+/// rather than a plain string.
+fn convert_content_parts(list: &Bound<'_, PyList>) -> PyResult<Vec<api::ContentPart>> {
+    use api::{ContentPart, ImageUrl};
+
+    let mut parts = Vec::new();
+    for item in list.iter() {
+        let part_dict: &Bound<'_, PyDict> = item.cast()?;
+        let part_type: String = part_dict
+            .get_item("type")?
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("content part missing 'type'"))?
+            .extract()?;
+
+        let part = match part_type.as_str() {
+            "text" => {
+                let text: String = part_dict
+                    .get_item("text")?
+                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("text content part missing 'text'"))?
+                    .extract()?;
+                ContentPart::Text { text }
+            }
+            "image_url" => {
+                let image_obj = part_dict.get_item("image_url")?.ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyKeyError, _>("image_url content part missing 'image_url'")
+                })?;
+                let image_dict: &Bound<'_, PyDict> = image_obj.cast()?;
+                let url: String = image_dict
+                    .get_item("url")?
+                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("image_url missing 'url'"))?
+                    .extract()?;
+                let detail: Option<String> = image_dict.get_item("detail")?.and_then(|v| v.extract().ok());
+                ContentPart::ImageUrl {
+                    image_url: ImageUrl { url, detail },
+                }
+            }
+            other => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "unsupported content part type '{}'",
+                    other
+                )))
+            }
+        };
+        parts.push(part);
+    }
+    Ok(parts)
+}
+
+/// Convert a Python `tool_choice` value into a `ToolChoice`: either one of
+/// the mode strings `"auto"`, `"none"`, `"required"`, or a dict naming a
+/// specific function (`{"type": "function", "function": {"name": ...}}`).
+fn convert_tool_choice(value: &Bound<'_, PyAny>) -> PyResult<ToolChoice> {
+    if let Ok(mode) = value.extract::<String>() {
+        return Ok(ToolChoice::Mode(mode));
+    }
+
+    let dict: &Bound<'_, PyDict> = value.cast().map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "tool_choice must be a string (\"auto\" | \"none\" | \"required\") or a \
+             {\"type\": \"function\", \"function\": {\"name\": ...}} dict",
+        )
+    })?;
+
+    let tool_type: String = dict
+        .get_item("type")?
+        .map(|v| v.extract().unwrap_or_else(|_| "function".to_string()))
+        .unwrap_or_else(|| "function".to_string());
+
+    let func_obj = dict
+        .get_item("function")?
+        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("missing 'function'"))?;
+    let func_dict: &Bound<'_, PyDict> = func_obj
+        .cast::<PyDict>()
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyTypeError, _>(e.to_string()))?;
+
+    let name: String = func_dict
+        .get_item("name")?
+        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("missing 'function.name'"))?
+        .extract()?;
+
+    Ok(ToolChoice::Function {
+        r#type: tool_type,
+        function: ToolChoiceFunction { name },
+    })
+}
+
+/// Convert Python list of tool schema dicts (OpenAI's `{"type": "function",
+/// "function": {"name", "description", "parameters"}}` shape) to Rust `Tool`s
+fn convert_tools(tools: &Bound<'_, PyList>) -> PyResult<Vec<api::Tool>> {
+    use api::{FunctionDefinition, Tool};
+
+    let mut result = Vec::new();
+
+    for item in tools.iter() {
+        let dict: &Bound<'_, PyDict> = item.cast()?;
+
+        let tool_type: String = dict
+            .get_item("type")?
+            .map(|v| v.extract().unwrap_or_else(|_| "function".to_string()))
+            .unwrap_or_else(|| "function".to_string());
+
+        let func_obj = dict
+            .get_item("function")?
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("missing 'function'"))?;
+        let func_dict: &Bound<'_, PyDict> = func_obj
+            .cast::<PyDict>()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyTypeError, _>(e.to_string()))?;
+
+        let name: String = func_dict
+            .get_item("name")?
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("missing 'function.name'"))?
+            .extract()?;
+
+        let description: Option<String> = func_dict.get_item("description")?.and_then(|v| v.extract().ok());
+
+        let parameters = match func_dict.get_item("parameters")? {
+            Some(params) => Some(python_to_json(&params)?),
+            None => None,
+        };
+
+        result.push(Tool {
+            tool_type,
+            function: FunctionDefinition {
+                name,
+                description,
+                parameters,
+            },
+        });
+    }
+
+    Ok(result)
+}
+
+/// Convert a `Message` into the Python dict shape exposed by `completion`/`completion_with_tools`
+fn message_to_pydict(py: Python<'_>, message: &Message) -> PyResult<Py<PyDict>> {
+    let message_dict = PyDict::new(py);
+    message_dict.set_item("role", &message.role)?;
+    message_dict.set_item("content", message.content.to_string_content())?;
+
+    // Also expose tool_calls if present
+    if let Some(tool_calls) = &message.tool_calls {
+        let tools_list = PyList::empty(py);
+        for tool in tool_calls {
+            let tool_dict = PyDict::new(py);
+            tool_dict.set_item("id", &tool.id)?;
+            tool_dict.set_item("type", &tool.call_type)?;
+
+            let func_dict = PyDict::new(py);
+            func_dict.set_item("name", &tool.function.name)?;
+            func_dict.set_item("arguments", &tool.function.arguments)?;
+
+            tool_dict.set_item("function", func_dict)?;
+            tools_list.append(tool_dict)?;
+        }
+        message_dict.set_item("tool_calls", tools_list)?;
+    }
+
+    if let Some(tool_call_id) = &message.tool_call_id {
+        message_dict.set_item("tool_call_id", tool_call_id)?;
+    }
+
+    Ok(message_dict.into())
+}
+
+/// Convert a `CompletionResponse` into the Python dict shape returned by `completion`
+fn completion_response_to_pydict(py: Python<'_>, response: &CompletionResponse) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", &response.id)?;
+    dict.set_item("object", &response.object)?;
+    dict.set_item("created", response.created)?;
+    dict.set_item("model", &response.model)?;
+
+    let choices = PyList::empty(py);
+    for choice in &response.choices {
+        let choice_dict = PyDict::new(py);
+        choice_dict.set_item("index", choice.index)?;
+        choice_dict.set_item("finish_reason", &choice.finish_reason)?;
+        choice_dict.set_item("message", message_to_pydict(py, &choice.message)?)?;
+        choices.append(choice_dict)?;
+    }
+    dict.set_item("choices", choices)?;
+
+    if let Some(usage) = &response.usage {
+        let usage_dict = PyDict::new(py);
+        usage_dict.set_item("prompt_tokens", usage.prompt_tokens)?;
+        usage_dict.set_item("completion_tokens", usage.completion_tokens)?;
+        usage_dict.set_item("total_tokens", usage.total_tokens)?;
+        dict.set_item("usage", usage_dict)?;
+    }
+
+    Ok(dict.into())
+}
+
 /// Convert Python object to serde_json::Value
 fn python_to_json(obj: &Bound<'_, pyo3::PyAny>) -> PyResult<serde_json::Value> {
     if obj.is_none() {
@@ -850,19 +1680,50 @@ fn python_to_json(obj: &Bound<'_, pyo3::PyAny>) -> PyResult<serde_json::Value> {
     }
 }
 
+/// Convert a serde_json::Value to a Python object (inverse of `python_to_json`)
+fn json_to_python(py: Python<'_>, value: &serde_json::Value) -> PyResult<Py<PyAny>> {
+    match value {
+        serde_json::Value::Null => Ok(py.None()),
+        serde_json::Value::Bool(b) => Ok(b.into_pyobject(py)?.into_any().unbind()),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(i.into_pyobject(py)?.into_any().unbind())
+            } else {
+                Ok(n.as_f64().unwrap_or(0.0).into_pyobject(py)?.into_any().unbind())
+            }
+        }
+        serde_json::Value::String(s) => Ok(s.as_str().into_pyobject(py)?.into_any().unbind()),
+        serde_json::Value::Array(arr) => {
+            let list = PyList::empty(py);
+            for item in arr {
+                list.append(json_to_python(py, item)?)?;
+            }
+            Ok(list.into())
+        }
+        serde_json::Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, val) in map {
+                dict.set_item(key, json_to_python(py, val)?)?;
+            }
+            Ok(dict.into())
+        }
+    }
+}
+
 /// Convenience function for quick completions
 #[pyfunction]
-#[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, **kwargs))]
+#[pyo3(signature = (messages, model=None, temperature=None, max_tokens=None, tool_choice=None, **kwargs))]
 fn completion(
     py: Python<'_>,
     messages: &Bound<'_, PyList>,
     model: Option<&str>,
     temperature: Option<f32>,
     max_tokens: Option<u32>,
+    tool_choice: Option<&Bound<'_, PyAny>>,
     kwargs: Option<&Bound<'_, PyDict>>,
 ) -> PyResult<Py<PyAny>> {
     let client = PyLlmClient::new(None, None)?;
-    client.completion(py, messages, model, temperature, max_tokens, None, kwargs)
+    client.completion(py, messages, model, temperature, max_tokens, None, tool_choice, kwargs)
 }
 
 /// Python module definition
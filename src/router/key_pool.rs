@@ -2,10 +2,94 @@
 //!
 //! Manages multiple API keys per provider with rotation strategies.
 
-use crate::config::RotationStrategy;
+use crate::config::{KeyMetadata, RateLimitConfig, RotationStrategy};
+use crate::router::strategy::ModelRoute;
 use parking_lot::RwLock;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::time::{Duration, Instant};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A token bucket enforcing a requests-per-minute-style limit client-side,
+/// so we stop sending requests we already know the server would reject.
+///
+/// `allowance` refills continuously at `limit / 60` units per second, up to
+/// `limit`, and is spent by [`TokenBucket::try_acquire`].
+#[derive(Debug)]
+struct TokenBucket {
+    /// Units available per minute; `None` means unlimited (always admits).
+    limit: Option<f64>,
+    state: RwLock<TokenBucketState>,
+}
+
+#[derive(Debug)]
+struct TokenBucketState {
+    allowance: f64,
+    last_checked: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: Option<u32>) -> Self {
+        let limit = limit.map(f64::from);
+        Self {
+            limit,
+            state: RwLock::new(TokenBucketState {
+                allowance: limit.unwrap_or(0.0),
+                last_checked: Instant::now(),
+            }),
+        }
+    }
+
+    /// Refill `state` for elapsed time and return the up-to-date allowance
+    /// without spending anything.
+    fn refill(&self, state: &mut TokenBucketState) -> f64 {
+        let Some(limit) = self.limit else {
+            return f64::INFINITY;
+        };
+
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(state.last_checked);
+        state.allowance = (state.allowance + elapsed.as_secs_f64() * (limit / 60.0)).min(limit);
+        state.last_checked = now;
+        state.allowance
+    }
+
+    /// Try to spend `cost` units. On success the allowance is reduced and
+    /// `Ok(())` is returned. On failure, returns the duration the caller
+    /// would need to wait before `cost` units become available.
+    fn try_acquire(&self, cost: f64) -> std::result::Result<(), Duration> {
+        if self.limit.is_none() {
+            return Ok(());
+        }
+
+        let mut state = self.state.write();
+        let allowance = self.refill(&mut state);
+
+        if allowance >= cost {
+            state.allowance -= cost;
+            Ok(())
+        } else {
+            let limit = self.limit.unwrap();
+            let wait = (cost - allowance) / (limit / 60.0);
+            Err(Duration::from_secs_f64(wait.max(0.0)))
+        }
+    }
+
+    /// Like `try_acquire`, but never spends the allowance.
+    fn time_until_available(&self, cost: f64) -> Duration {
+        let Some(limit) = self.limit else {
+            return Duration::ZERO;
+        };
+
+        let mut state = self.state.write();
+        let allowance = self.refill(&mut state);
+
+        if allowance >= cost {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(((cost - allowance) / (limit / 60.0)).max(0.0))
+        }
+    }
+}
 
 /// A single API key with usage tracking
 #[derive(Debug)]
@@ -21,16 +105,63 @@ pub struct ApiKey {
 
     /// Timestamp of last usage (for LRU strategy)
     last_used: AtomicU64,
+
+    /// Proactive client-side limit on requests per minute
+    request_bucket: TokenBucket,
+
+    /// Proactive client-side limit on tokens per minute
+    token_bucket: TokenBucket,
+
+    /// Which models this key may be used for, and when it expires
+    metadata: KeyMetadata,
+
+    /// Relative capacity for `RotationStrategy::Weighted`: `metadata.weight`
+    /// if set, otherwise `rate_limit.requests_per_minute`, otherwise `1`.
+    weight: u32,
+
+    /// Number of consecutive times this key has been rate limited without
+    /// an explicit `retry-after`, driving the exponential backoff in
+    /// [`ApiKey::mark_rate_limited`]. Reset by [`ApiKey::record_usage`].
+    consecutive_limits: AtomicU32,
+
+    /// Ceiling for the exponential backoff computed when no explicit
+    /// `retry-after` is given, from `rate_limit.max_backoff_secs`.
+    max_backoff: Duration,
 }
 
+/// Starting point for the exponential backoff applied when a provider
+/// returns a rate-limit error without a `retry-after` duration.
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// Default ceiling for that backoff when `RateLimitConfig::max_backoff_secs`
+/// isn't set.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
 impl ApiKey {
-    /// Create a new API key
-    pub fn new(value: String) -> Self {
+    /// Create a new API key, optionally enforcing `rate_limit`'s
+    /// requests-per-minute and tokens-per-minute limits client-side and
+    /// `metadata`'s model scope and expiry.
+    pub fn new(value: String, rate_limit: Option<&RateLimitConfig>, metadata: KeyMetadata) -> Self {
+        let weight = metadata
+            .weight
+            .or_else(|| rate_limit.and_then(|rl| rl.requests_per_minute))
+            .unwrap_or(1);
+        let max_backoff = rate_limit
+            .and_then(|rl| rl.max_backoff_secs)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_MAX_BACKOFF);
+
         Self {
             value,
             rate_limited_until: RwLock::new(None),
             request_count: AtomicU64::new(0),
             last_used: AtomicU64::new(0),
+            request_bucket: TokenBucket::new(rate_limit.and_then(|rl| rl.requests_per_minute)),
+            token_bucket: TokenBucket::new(rate_limit.and_then(|rl| rl.tokens_per_minute)),
+            metadata,
+            weight,
+            consecutive_limits: AtomicU32::new(0),
+            max_backoff,
         }
     }
 
@@ -39,6 +170,11 @@ impl ApiKey {
         &self.value
     }
 
+    /// Relative capacity used by `RotationStrategy::Weighted`
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
     /// Check if this key is currently rate limited
     pub fn is_rate_limited(&self) -> bool {
         let guard = self.rate_limited_until.read();
@@ -64,10 +200,31 @@ impl ApiKey {
         }
     }
 
-    /// Mark this key as rate limited
-    pub fn mark_rate_limited(&self, duration: Duration) {
+    /// Mark this key as rate limited. If the provider gave us an explicit
+    /// `retry-after`, `duration` should be `Some` and is trusted verbatim.
+    /// If it didn't (a bare 429), pass `None`: the wait is instead computed
+    /// as `BACKOFF_BASE * 2^consecutive_limits`, capped at `max_backoff` and
+    /// jittered up to 20% so a pool of keys limited at the same instant
+    /// doesn't all retry in lockstep. The counter only advances on this
+    /// `None` path and is reset by [`ApiKey::record_usage`].
+    pub fn mark_rate_limited(&self, duration: Option<Duration>) {
+        let wait = duration.unwrap_or_else(|| {
+            let attempt = self.consecutive_limits.fetch_add(1, Ordering::Relaxed);
+            self.backoff_for(attempt)
+        });
         let mut guard = self.rate_limited_until.write();
-        *guard = Some(Instant::now() + duration);
+        *guard = Some(Instant::now() + wait);
+    }
+
+    /// Exponential backoff for the `attempt`-th consecutive bare rate limit,
+    /// capped at `max_backoff` and jittered up to 20% longer.
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+
+        let capped = BACKOFF_BASE.saturating_mul(1u32 << attempt.min(31)).min(self.max_backoff);
+        let jitter_fraction = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        capped + capped.mul_f64(jitter_fraction * 0.2)
     }
 
     /// Clear rate limit status
@@ -76,11 +233,13 @@ impl ApiKey {
         *guard = None;
     }
 
-    /// Record usage of this key
+    /// Record usage of this key, resetting the adaptive backoff counter so a
+    /// successful request after recovering from a limit starts clean.
     pub fn record_usage(&self) {
         self.request_count.fetch_add(1, Ordering::Relaxed);
         let now = Instant::now().elapsed().as_secs();
         self.last_used.store(now, Ordering::Relaxed);
+        self.consecutive_limits.store(0, Ordering::Relaxed);
     }
 
     /// Get the request count
@@ -92,6 +251,64 @@ impl ApiKey {
     pub fn last_used(&self) -> u64 {
         self.last_used.load(Ordering::Relaxed)
     }
+
+    /// Try to admit a request costing 1 unit of the request bucket and
+    /// (if given) `estimated_tokens` units of the token bucket. On success,
+    /// both buckets are spent. On failure, neither bucket is spent, and the
+    /// duration until the limiting bucket would admit the request is returned.
+    pub fn try_acquire(&self, estimated_tokens: Option<u32>) -> std::result::Result<(), Duration> {
+        let token_cost = estimated_tokens.map(f64::from).unwrap_or(0.0);
+
+        let request_wait = self.request_bucket.time_until_available(1.0);
+        let token_wait = self.token_bucket.time_until_available(token_cost);
+
+        if request_wait > Duration::ZERO || token_wait > Duration::ZERO {
+            return Err(request_wait.max(token_wait));
+        }
+
+        // Both buckets currently admit; actually spend them. A concurrent
+        // acquirer could in principle slip in between the checks above and
+        // here, but worst case that just means an occasional extra wait on
+        // the next call, not an unbounded overdraw.
+        self.request_bucket.try_acquire(1.0).ok();
+        self.token_bucket.try_acquire(token_cost).ok();
+        Ok(())
+    }
+
+    /// Duration until [`ApiKey::try_acquire`] would admit a request for
+    /// `estimated_tokens`, without spending anything.
+    pub fn time_until_available(&self, estimated_tokens: Option<u32>) -> Duration {
+        let token_cost = estimated_tokens.map(f64::from).unwrap_or(0.0);
+        self.request_bucket
+            .time_until_available(1.0)
+            .max(self.token_bucket.time_until_available(token_cost))
+    }
+
+    /// Whether this key has passed its `expires_at`, if any.
+    pub fn is_expired(&self) -> bool {
+        match self.metadata.expires_at {
+            Some(expiry) => SystemTime::now() >= expiry,
+            None => false,
+        }
+    }
+
+    /// Whether this key is in scope for `model`: unexpired, and either
+    /// unrestricted or matching one of its `allowed_models` patterns.
+    pub fn is_usable_for(&self, model: &ModelRoute) -> bool {
+        if self.is_expired() {
+            return false;
+        }
+
+        if self.metadata.allowed_models.is_empty() {
+            return true;
+        }
+
+        let model_id = model.model_id();
+        self.metadata.allowed_models.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => model_id.starts_with(prefix),
+            None => pattern == &model_id,
+        })
+    }
 }
 
 /// Pool of API keys with rotation support
@@ -111,11 +328,26 @@ pub struct KeyPool {
 }
 
 impl KeyPool {
-    /// Create a new key pool
-    pub fn new(provider: String, keys: Vec<String>, strategy: RotationStrategy) -> Self {
+    /// Create a new key pool, optionally enforcing `rate_limit`'s
+    /// client-side request/token buckets on every key and `key_metadata`'s
+    /// per-key model scope and expiry (keyed by the key's literal value;
+    /// keys absent from the map are unrestricted).
+    pub fn new(
+        provider: String,
+        keys: Vec<String>,
+        strategy: RotationStrategy,
+        rate_limit: Option<&RateLimitConfig>,
+        key_metadata: &std::collections::HashMap<String, KeyMetadata>,
+    ) -> Self {
         Self {
             provider,
-            keys: keys.into_iter().map(ApiKey::new).collect(),
+            keys: keys
+                .into_iter()
+                .map(|k| {
+                    let metadata = key_metadata.get(&k).cloned().unwrap_or_default();
+                    ApiKey::new(k, rate_limit, metadata)
+                })
+                .collect(),
             current_index: AtomicUsize::new(0),
             strategy,
         }
@@ -136,21 +368,46 @@ impl KeyPool {
         self.keys.len()
     }
 
-    /// Get the next available key based on rotation strategy
-    pub fn get_key(&self) -> Option<&ApiKey> {
+    /// Get the next key usable for `model`, based on rotation strategy.
+    /// Keys that are expired or whose `allowed_models` scope doesn't cover
+    /// `model` are excluded entirely (`None` if every key is excluded this
+    /// way). Among the rest, prefers one whose request/token buckets
+    /// currently admit the request; if one is chosen this way, its buckets
+    /// are spent. Falls back to the in-scope key that will become available
+    /// soonest when every in-scope key is currently rate limited or
+    /// bucket-throttled; that key's buckets are spent only if they happen to
+    /// admit the request at that moment.
+    /// `estimated_tokens`, if given, is also charged against each
+    /// candidate's token bucket (see [`ApiKey::try_acquire`]) so a pool
+    /// doesn't hand out a key it already knows can't afford the request.
+    pub fn get_key_for(&self, model: &ModelRoute, estimated_tokens: Option<u32>) -> Option<&ApiKey> {
         if self.keys.is_empty() {
             return None;
         }
 
-        match self.strategy {
-            RotationStrategy::RoundRobin => self.get_round_robin(),
-            RotationStrategy::LeastRecentlyUsed => self.get_lru(),
-            RotationStrategy::Random => self.get_random(),
-        }
+        let key = match self.strategy {
+            RotationStrategy::RoundRobin => self.get_round_robin(model, estimated_tokens),
+            RotationStrategy::LeastRecentlyUsed => self.get_lru(model, estimated_tokens),
+            RotationStrategy::Random => self.get_random(model, estimated_tokens),
+            RotationStrategy::Weighted => self.get_weighted(model, estimated_tokens),
+        }?;
+
+        // Ignore the result: if `key` came from the soonest-available
+        // fallback it may not actually admit yet, and that's fine — callers
+        // use `time_until_available`/`min_wait_time` to know to wait.
+        let _ = key.try_acquire(estimated_tokens);
+        Some(key)
+    }
+
+    /// Whether `key` is currently usable: not server-rate-limited and its
+    /// client-side buckets (including the token bucket, if `estimated_tokens`
+    /// is given) admit a request right now.
+    fn admits(key: &ApiKey, estimated_tokens: Option<u32>) -> bool {
+        !key.is_rate_limited() && key.time_until_available(estimated_tokens) == Duration::ZERO
     }
 
     /// Round-robin key selection
-    fn get_round_robin(&self) -> Option<&ApiKey> {
+    fn get_round_robin(&self, model: &ModelRoute, estimated_tokens: Option<u32>) -> Option<&ApiKey> {
         let len = self.keys.len();
         let mut attempts = 0;
 
@@ -158,35 +415,39 @@ impl KeyPool {
             let idx = self.current_index.fetch_add(1, Ordering::Relaxed) % len;
             let key = &self.keys[idx];
 
-            if !key.is_rate_limited() {
+            if key.is_usable_for(model) && Self::admits(key, estimated_tokens) {
                 return Some(key);
             }
 
             attempts += 1;
         }
 
-        // All keys are rate limited, return the one that will be available soonest
-        self.get_soonest_available()
+        // All in-scope keys are rate limited, return the one that will be available soonest
+        self.get_soonest_available(model)
     }
 
     /// LRU key selection
-    fn get_lru(&self) -> Option<&ApiKey> {
+    fn get_lru(&self, model: &ModelRoute, estimated_tokens: Option<u32>) -> Option<&ApiKey> {
         self.keys
             .iter()
-            .filter(|k| !k.is_rate_limited())
+            .filter(|k| k.is_usable_for(model) && Self::admits(k, estimated_tokens))
             .min_by_key(|k| k.last_used())
-            .or_else(|| self.get_soonest_available())
+            .or_else(|| self.get_soonest_available(model))
     }
 
     /// Random key selection
-    fn get_random(&self) -> Option<&ApiKey> {
+    fn get_random(&self, model: &ModelRoute, estimated_tokens: Option<u32>) -> Option<&ApiKey> {
         use std::collections::hash_map::RandomState;
         use std::hash::{BuildHasher, Hasher};
 
-        let available: Vec<_> = self.keys.iter().filter(|k| !k.is_rate_limited()).collect();
+        let available: Vec<_> = self
+            .keys
+            .iter()
+            .filter(|k| k.is_usable_for(model) && Self::admits(k, estimated_tokens))
+            .collect();
 
         if available.is_empty() {
-            return self.get_soonest_available();
+            return self.get_soonest_available(model);
         }
 
         // Simple pseudo-random selection
@@ -195,20 +456,74 @@ impl KeyPool {
         Some(available[idx])
     }
 
-    /// Get the key that will be available soonest
-    fn get_soonest_available(&self) -> Option<&ApiKey> {
+    /// Weighted random key selection: draws among in-scope, non-throttled
+    /// keys with probability proportional to `ApiKey::weight`, via a
+    /// prefix-sum over a single random draw in `[0, total_weight)`.
+    fn get_weighted(&self, model: &ModelRoute, estimated_tokens: Option<u32>) -> Option<&ApiKey> {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+
+        let available: Vec<_> = self
+            .keys
+            .iter()
+            .filter(|k| k.is_usable_for(model) && Self::admits(k, estimated_tokens))
+            .collect();
+
+        if available.is_empty() {
+            return self.get_soonest_available(model);
+        }
+
+        let total_weight: u64 = available.iter().map(|k| u64::from(k.weight())).sum();
+        if total_weight == 0 {
+            // No key carries any weight; fall back to uniform selection.
+            let hasher = RandomState::new().build_hasher();
+            let idx = hasher.finish() as usize % available.len();
+            return Some(available[idx]);
+        }
+
+        let hasher = RandomState::new().build_hasher();
+        let mut draw = hasher.finish() % total_weight;
+
+        for key in &available {
+            let weight = u64::from(key.weight());
+            if draw < weight {
+                return Some(key);
+            }
+            draw -= weight;
+        }
+
+        // Unreachable in practice: `draw < total_weight` by construction.
+        available.last().copied()
+    }
+
+    /// Get the in-scope-for-`model` key that will be available soonest,
+    /// across both the server-reported rate limit and the client-side
+    /// buckets.
+    fn get_soonest_available(&self, model: &ModelRoute) -> Option<&ApiKey> {
         self.keys
             .iter()
-            .min_by_key(|k| k.rate_limit_remaining().unwrap_or(Duration::ZERO))
+            .filter(|k| k.is_usable_for(model))
+            .min_by_key(|k| {
+                k.rate_limit_remaining().unwrap_or(Duration::ZERO).max(k.time_until_available(None))
+            })
     }
 
-    /// Mark a specific key as rate limited
-    pub fn mark_rate_limited(&self, key_value: &str, duration: Duration) {
+    /// Mark a specific key as rate limited. See [`ApiKey::mark_rate_limited`]
+    /// for how `duration` of `None` triggers adaptive backoff.
+    pub fn mark_rate_limited(&self, key_value: &str, duration: Option<Duration>) {
         if let Some(key) = self.keys.iter().find(|k| k.value() == key_value) {
             key.mark_rate_limited(duration);
         }
     }
 
+    /// Record a successful request against a specific key. See
+    /// [`ApiKey::record_usage`] for how this resets its adaptive backoff.
+    pub fn record_usage(&self, key_value: &str) {
+        if let Some(key) = self.keys.iter().find(|k| k.value() == key_value) {
+            key.record_usage();
+        }
+    }
+
     /// Check if all keys are currently rate limited
     pub fn all_rate_limited(&self) -> bool {
         self.keys.iter().all(|k| k.is_rate_limited())
@@ -222,6 +537,17 @@ impl KeyPool {
             .min()
     }
 
+    /// Duration until the client-side buckets of the soonest key would admit
+    /// a request, so callers can sleep instead of spinning on `get_key_for`.
+    /// `Duration::ZERO` if at least one key currently admits.
+    pub fn time_until_available(&self) -> Duration {
+        self.keys
+            .iter()
+            .map(|k| k.time_until_available(None))
+            .min()
+            .unwrap_or(Duration::ZERO)
+    }
+
     /// Get statistics about the pool
     pub fn stats(&self) -> KeyPoolStats {
         let total = self.keys.len();
@@ -235,10 +561,25 @@ impl KeyPool {
             total_requests,
         }
     }
+
+    /// Per-key snapshot for external observability, identified by `key_index`
+    /// rather than the key value so the raw secret never leaves this process.
+    pub fn key_snapshots(&self) -> Vec<KeySnapshot> {
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(key_index, key)| KeySnapshot {
+                key_index,
+                request_count: key.request_count(),
+                rate_limited: key.is_rate_limited(),
+                rate_limit_remaining_secs: key.rate_limit_remaining().map(|d| d.as_secs_f64()),
+            })
+            .collect()
+    }
 }
 
 /// Statistics about a key pool
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct KeyPoolStats {
     pub total_keys: usize,
     pub available_keys: usize,
@@ -246,17 +587,33 @@ pub struct KeyPoolStats {
     pub total_requests: u64,
 }
 
+/// Point-in-time snapshot of a single key, for external observability. Uses
+/// `key_index` (the key's position in the pool) rather than the key value
+/// itself, which is never exposed outside this process.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeySnapshot {
+    pub key_index: usize,
+    pub request_count: u64,
+    pub rate_limited: bool,
+    pub rate_limit_remaining_secs: Option<f64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+
+    fn any_model() -> ModelRoute {
+        ModelRoute::parse("test/gpt-4o").unwrap()
+    }
 
     #[test]
     fn test_key_rate_limiting() {
-        let key = ApiKey::new("test-key".to_string());
+        let key = ApiKey::new("test-key".to_string(), None, KeyMetadata::default());
 
         assert!(!key.is_rate_limited());
 
-        key.mark_rate_limited(Duration::from_secs(60));
+        key.mark_rate_limited(Some(Duration::from_secs(60)));
         assert!(key.is_rate_limited());
 
         key.clear_rate_limit();
@@ -269,12 +626,14 @@ mod tests {
             "test".to_string(),
             vec!["key1".to_string(), "key2".to_string(), "key3".to_string()],
             RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
         );
 
-        let k1 = pool.get_key().unwrap();
-        let k2 = pool.get_key().unwrap();
-        let k3 = pool.get_key().unwrap();
-        let k4 = pool.get_key().unwrap();
+        let k1 = pool.get_key_for(&any_model(), None).unwrap();
+        let k2 = pool.get_key_for(&any_model(), None).unwrap();
+        let k3 = pool.get_key_for(&any_model(), None).unwrap();
+        let k4 = pool.get_key_for(&any_model(), None).unwrap();
 
         // Should cycle through keys
         assert_eq!(k1.value(), "key1");
@@ -289,13 +648,15 @@ mod tests {
             "test".to_string(),
             vec!["key1".to_string(), "key2".to_string()],
             RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
         );
 
         // Rate limit the first key
-        pool.mark_rate_limited("key1", Duration::from_secs(60));
+        pool.mark_rate_limited("key1", Some(Duration::from_secs(60)));
 
         // Should skip key1
-        let k = pool.get_key().unwrap();
+        let k = pool.get_key_for(&any_model(), None).unwrap();
         assert_eq!(k.value(), "key2");
     }
 
@@ -305,13 +666,337 @@ mod tests {
             "test".to_string(),
             vec!["key1".to_string(), "key2".to_string()],
             RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
         );
 
         assert!(!pool.all_rate_limited());
 
-        pool.mark_rate_limited("key1", Duration::from_secs(60));
-        pool.mark_rate_limited("key2", Duration::from_secs(60));
+        pool.mark_rate_limited("key1", Some(Duration::from_secs(60)));
+        pool.mark_rate_limited("key2", Some(Duration::from_secs(60)));
 
         assert!(pool.all_rate_limited());
     }
+
+    #[test]
+    fn test_token_bucket_blocks_once_allowance_is_spent() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: Some(60),
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        };
+        let key = ApiKey::new("test-key".to_string(), Some(&rate_limit), KeyMetadata::default());
+
+        // Allowance starts full (60 requests), so the first acquire succeeds
+        // and the bucket is down to 59/60 - still plenty of room.
+        assert!(key.try_acquire(None).is_ok());
+
+        // Drain the rest of the allowance.
+        for _ in 0..59 {
+            assert!(key.try_acquire(None).is_ok());
+        }
+
+        // Bucket is now empty; next request must wait.
+        let err = key.try_acquire(None).unwrap_err();
+        assert!(err > Duration::ZERO);
+        assert!(err <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_token_bucket_unlimited_always_admits() {
+        let key = ApiKey::new("test-key".to_string(), None, KeyMetadata::default());
+
+        for _ in 0..1000 {
+            assert!(key.try_acquire(Some(1_000_000)).is_ok());
+        }
+        assert_eq!(key.time_until_available(Some(1_000_000)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_key_pool_time_until_available_prefers_admitting_key() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: Some(1),
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        };
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["key1".to_string(), "key2".to_string()],
+            RotationStrategy::RoundRobin,
+            Some(&rate_limit),
+            &HashMap::new(),
+        );
+
+        // Exhaust key1's single-request allowance directly.
+        let key1 = pool.keys.iter().find(|k| k.value() == "key1").unwrap();
+        assert!(key1.try_acquire(None).is_ok());
+
+        // key2 still admits, so the pool as a whole is available right now.
+        assert_eq!(pool.time_until_available(), Duration::ZERO);
+
+        // The pool should route around key1 and hand back key2, which still admits.
+        let chosen = pool.get_key_for(&any_model(), None).unwrap();
+        assert_eq!(chosen.value(), "key2");
+    }
+
+    #[test]
+    fn test_get_key_for_routes_around_key_that_cant_afford_estimated_tokens() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: None,
+            tokens_per_minute: Some(1000),
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        };
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["key1".to_string(), "key2".to_string()],
+            RotationStrategy::RoundRobin,
+            Some(&rate_limit),
+            &HashMap::new(),
+        );
+
+        // Spend nearly all of key1's token allowance directly.
+        let key1 = pool.keys.iter().find(|k| k.value() == "key1").unwrap();
+        assert!(key1.try_acquire(Some(900)).is_ok());
+
+        // A request estimated at 500 tokens can't fit in key1's remaining
+        // ~100, so the pool should route it to key2 instead.
+        let chosen = pool.get_key_for(&any_model(), Some(500)).unwrap();
+        assert_eq!(chosen.value(), "key2");
+    }
+
+    #[test]
+    fn test_get_key_for_skips_keys_outside_allowed_models() {
+        let mut key_metadata = HashMap::new();
+        key_metadata.insert(
+            "narrow".to_string(),
+            KeyMetadata {
+                allowed_models: vec!["gpt-4o".to_string()],
+                expires_at: None,
+                weight: None,
+            },
+        );
+
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["narrow".to_string(), "broad".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &key_metadata,
+        );
+
+        // "narrow" only covers gpt-4o, so a request for a cheaper model must
+        // route to "broad" even though "narrow" is unthrottled.
+        let cheap_model = ModelRoute::parse("test/gpt-4o-mini").unwrap();
+        let chosen = pool.get_key_for(&cheap_model, None).unwrap();
+        assert_eq!(chosen.value(), "broad");
+
+        // A request for the scoped model can still use "narrow".
+        let k1 = pool.get_key_for(&any_model(), None).unwrap();
+        let k2 = pool.get_key_for(&any_model(), None).unwrap();
+        assert!([k1.value(), k2.value()].contains(&"narrow"));
+    }
+
+    #[test]
+    fn test_get_key_for_skips_expired_keys() {
+        let mut key_metadata = HashMap::new();
+        key_metadata.insert(
+            "expired".to_string(),
+            KeyMetadata {
+                allowed_models: vec![],
+                expires_at: Some(SystemTime::now() - Duration::from_secs(1)),
+                weight: None,
+            },
+        );
+
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["expired".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &key_metadata,
+        );
+
+        assert!(pool.get_key_for(&any_model(), None).is_none());
+    }
+
+    #[test]
+    fn test_allowed_models_supports_prefix_wildcard() {
+        let key = ApiKey::new(
+            "key".to_string(),
+            None,
+            KeyMetadata {
+                allowed_models: vec!["gpt-4*".to_string()],
+                expires_at: None,
+                weight: None,
+            },
+        );
+
+        assert!(key.is_usable_for(&ModelRoute::parse("test/gpt-4o").unwrap()));
+        assert!(key.is_usable_for(&ModelRoute::parse("test/gpt-4-turbo").unwrap()));
+        assert!(!key.is_usable_for(&ModelRoute::parse("test/claude-3").unwrap()));
+    }
+
+    #[test]
+    fn test_weight_defaults_to_requests_per_minute_then_one() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: Some(500),
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: None,
+        };
+        let inferred = ApiKey::new("a".to_string(), Some(&rate_limit), KeyMetadata::default());
+        assert_eq!(inferred.weight(), 500);
+
+        let explicit = ApiKey::new(
+            "b".to_string(),
+            Some(&rate_limit),
+            KeyMetadata {
+                allowed_models: vec![],
+                expires_at: None,
+                weight: Some(7),
+            },
+        );
+        assert_eq!(explicit.weight(), 7);
+
+        let unset = ApiKey::new("c".to_string(), None, KeyMetadata::default());
+        assert_eq!(unset.weight(), 1);
+    }
+
+    #[test]
+    fn test_weighted_selection_favors_higher_weight_key() {
+        let mut key_metadata = HashMap::new();
+        key_metadata.insert(
+            "big".to_string(),
+            KeyMetadata {
+                allowed_models: vec![],
+                expires_at: None,
+                weight: Some(99),
+            },
+        );
+        key_metadata.insert(
+            "small".to_string(),
+            KeyMetadata {
+                allowed_models: vec![],
+                expires_at: None,
+                weight: Some(1),
+            },
+        );
+
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["big".to_string(), "small".to_string()],
+            RotationStrategy::Weighted,
+            None,
+            &key_metadata,
+        );
+
+        let big_hits = (0..200).filter(|_| pool.get_key_for(&any_model(), None).unwrap().value() == "big").count();
+
+        // With a 99:1 weight split, "big" should win the overwhelming majority
+        // of draws; this is a statistical property, not exact, so just assert
+        // it dominates rather than pinning a precise count.
+        assert!(big_hits > 150, "expected 'big' to dominate weighted draws, got {big_hits}/200");
+    }
+
+    #[test]
+    fn test_weighted_selection_falls_back_to_soonest_available_when_all_limited() {
+        let pool = KeyPool::new(
+            "test".to_string(),
+            vec!["key1".to_string(), "key2".to_string()],
+            RotationStrategy::Weighted,
+            None,
+            &HashMap::new(),
+        );
+
+        pool.mark_rate_limited("key1", Some(Duration::from_secs(60)));
+        pool.mark_rate_limited("key2", Some(Duration::from_secs(5)));
+
+        let chosen = pool.get_key_for(&any_model(), None).unwrap();
+        assert_eq!(chosen.value(), "key2");
+    }
+
+    #[test]
+    fn test_explicit_retry_after_is_trusted_verbatim() {
+        let key = ApiKey::new("test-key".to_string(), None, KeyMetadata::default());
+
+        key.mark_rate_limited(Some(Duration::from_secs(45)));
+        let remaining = key.rate_limit_remaining().unwrap();
+        assert!(remaining > Duration::from_secs(40) && remaining <= Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_bare_rate_limit_backs_off_exponentially() {
+        let key = ApiKey::new("test-key".to_string(), None, KeyMetadata::default());
+
+        key.mark_rate_limited(None);
+        let first = key.rate_limit_remaining().unwrap();
+        key.clear_rate_limit();
+
+        key.mark_rate_limited(None);
+        let second = key.rate_limit_remaining().unwrap();
+        key.clear_rate_limit();
+
+        key.mark_rate_limited(None);
+        let third = key.rate_limit_remaining().unwrap();
+
+        // Each bare limit at least doubles the wait (modulo jitter), since
+        // `consecutive_limits` advances every time.
+        assert!(second > first);
+        assert!(third > second);
+    }
+
+    #[test]
+    fn test_bare_rate_limit_backoff_is_capped() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: Some(10),
+        };
+        let key = ApiKey::new("test-key".to_string(), Some(&rate_limit), KeyMetadata::default());
+
+        for _ in 0..10 {
+            key.mark_rate_limited(None);
+            let remaining = key.rate_limit_remaining().unwrap();
+            // 20% jitter on top of a 10s ceiling
+            assert!(remaining <= Duration::from_secs(12));
+            key.clear_rate_limit();
+        }
+    }
+
+    #[test]
+    fn test_record_usage_resets_backoff_counter() {
+        let rate_limit = RateLimitConfig {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            retry_after_header: None,
+            remaining_requests_header: None,
+            reset_header: None,
+            max_backoff_secs: Some(3600),
+        };
+        let key = ApiKey::new("test-key".to_string(), Some(&rate_limit), KeyMetadata::default());
+
+        key.mark_rate_limited(None);
+        key.mark_rate_limited(None);
+        key.clear_rate_limit();
+        key.record_usage();
+
+        // Counter reset, so the next bare limit is back to the first-attempt wait.
+        key.mark_rate_limited(None);
+        let remaining = key.rate_limit_remaining().unwrap();
+        assert!(remaining <= Duration::from_secs(2));
+    }
 }
@@ -3,7 +3,9 @@
 //! Handles model routing and API key pool management.
 
 pub mod key_pool;
+pub mod registry;
 pub mod strategy;
 
-pub use key_pool::{ApiKey, KeyPool, KeyPoolStats};
+pub use key_pool::{ApiKey, KeyPool, KeyPoolStats, KeySnapshot};
+pub use registry::{PoolRegistry, ProviderSnapshot};
 pub use strategy::ModelRoute;
@@ -0,0 +1,168 @@
+//! Pool Registry
+//!
+//! Aggregates every provider's `KeyPool` into one cross-provider snapshot,
+//! and renders it as Prometheus text exposition format so operators can
+//! scrape pool health (request counts, rate-limit state, wait times) the
+//! way an admin metrics endpoint exposes bucket/key state. Entirely
+//! read-only over the existing `KeyPool`/`ApiKey` accessors; it owns no
+//! state of its own.
+
+use crate::router::key_pool::{KeyPool, KeyPoolStats, KeySnapshot};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// Snapshot of one provider's key pool at a point in time.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderSnapshot {
+    pub provider: String,
+    pub stats: KeyPoolStats,
+    pub min_wait_time_secs: Option<f64>,
+    pub keys: Vec<KeySnapshot>,
+}
+
+/// Read-only aggregation of every registered provider's `KeyPool`, for
+/// observability across the whole client rather than one pool at a time.
+#[derive(Debug, Default)]
+pub struct PoolRegistry<'a> {
+    pools: Vec<(&'a str, &'a KeyPool)>,
+}
+
+impl<'a> PoolRegistry<'a> {
+    /// Build a registry over `pools`, typically every provider's `KeyPool`
+    /// known to the caller.
+    pub fn new(pools: impl IntoIterator<Item = (&'a str, &'a KeyPool)>) -> Self {
+        Self {
+            pools: pools.into_iter().collect(),
+        }
+    }
+
+    /// A serializable snapshot of every registered pool.
+    pub fn snapshot(&self) -> Vec<ProviderSnapshot> {
+        self.pools
+            .iter()
+            .map(|(provider, pool)| ProviderSnapshot {
+                provider: (*provider).to_string(),
+                stats: pool.stats(),
+                min_wait_time_secs: pool.min_wait_time().map(|d| d.as_secs_f64()),
+                keys: pool.key_snapshots(),
+            })
+            .collect()
+    }
+
+    /// Render every registered pool as Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "# HELP llmao_key_requests_total Total requests made with a given key.").ok();
+        writeln!(out, "# TYPE llmao_key_requests_total counter").ok();
+        for (provider, pool) in &self.pools {
+            for key in pool.key_snapshots() {
+                writeln!(
+                    out,
+                    "llmao_key_requests_total{{provider=\"{provider}\",key_index=\"{}\"}} {}",
+                    key.key_index, key.request_count
+                )
+                .ok();
+            }
+        }
+
+        writeln!(out, "# HELP llmao_keys_rate_limited Number of keys currently rate limited, per provider.").ok();
+        writeln!(out, "# TYPE llmao_keys_rate_limited gauge").ok();
+        for (provider, pool) in &self.pools {
+            writeln!(
+                out,
+                "llmao_keys_rate_limited{{provider=\"{provider}\"}} {}",
+                pool.stats().rate_limited_keys
+            )
+            .ok();
+        }
+
+        writeln!(out, "# HELP llmao_pool_min_wait_seconds Minimum wait until a key is available, per provider.").ok();
+        writeln!(out, "# TYPE llmao_pool_min_wait_seconds gauge").ok();
+        for (provider, pool) in &self.pools {
+            let wait = pool.min_wait_time().map(|d| d.as_secs_f64()).unwrap_or(0.0);
+            writeln!(out, "llmao_pool_min_wait_seconds{{provider=\"{provider}\"}} {wait}").ok();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RotationStrategy;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_snapshot_aggregates_every_provider() {
+        let openai = KeyPool::new(
+            "openai".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
+        );
+        let anthropic = KeyPool::new(
+            "anthropic".to_string(),
+            vec!["c".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
+        );
+        anthropic.mark_rate_limited("c", Some(Duration::from_secs(30)));
+
+        let registry = PoolRegistry::new([("openai", &openai), ("anthropic", &anthropic)]);
+        let snapshot = registry.snapshot();
+
+        let openai_snapshot = snapshot.iter().find(|s| s.provider == "openai").unwrap();
+        assert_eq!(openai_snapshot.stats.total_keys, 2);
+        assert_eq!(openai_snapshot.keys.len(), 2);
+
+        let anthropic_snapshot = snapshot.iter().find(|s| s.provider == "anthropic").unwrap();
+        assert_eq!(anthropic_snapshot.stats.rate_limited_keys, 1);
+        assert!(anthropic_snapshot.min_wait_time_secs.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_render_metrics_includes_every_provider_and_key() {
+        let pool = KeyPool::new(
+            "openai".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
+        );
+        let registry = PoolRegistry::new([("openai", &pool)]);
+
+        let rendered = registry.render_metrics();
+
+        assert!(rendered.contains(r#"llmao_key_requests_total{provider="openai",key_index="0"}"#));
+        assert!(rendered.contains(r#"llmao_key_requests_total{provider="openai",key_index="1"}"#));
+        assert!(rendered.contains(r#"llmao_keys_rate_limited{provider="openai"} 0"#));
+        assert!(rendered.contains(r#"llmao_pool_min_wait_seconds{provider="openai"} 0"#));
+    }
+
+    #[test]
+    fn test_render_metrics_request_count_advances_on_real_usage() {
+        let pool = KeyPool::new(
+            "openai".to_string(),
+            vec!["a".to_string()],
+            RotationStrategy::RoundRobin,
+            None,
+            &HashMap::new(),
+        );
+        let registry = PoolRegistry::new([("openai", &pool)]);
+        assert!(registry
+            .render_metrics()
+            .contains(r#"llmao_key_requests_total{provider="openai",key_index="0"} 0"#));
+
+        // Drive the same success-path call `completion_on_route` makes.
+        pool.record_usage("a");
+
+        assert!(registry
+            .render_metrics()
+            .contains(r#"llmao_key_requests_total{provider="openai",key_index="0"} 1"#));
+    }
+}
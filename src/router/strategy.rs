@@ -3,6 +3,7 @@
 //! Handles parsing and routing of model identifiers.
 
 use crate::error::{LlmaoError, Result};
+use std::collections::HashMap;
 
 /// Parsed model identifier
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -48,6 +49,19 @@ impl ModelRoute {
             self.model.clone()
         }
     }
+
+    /// Resolve `model_string` into an ordered failover chain. If it names a
+    /// configured alias in `routes`, each listed `provider/model` target is
+    /// parsed in order and tried in turn; otherwise `model_string` itself is
+    /// parsed as the sole target.
+    pub fn parse_chain(model_string: &str, routes: &HashMap<String, Vec<String>>) -> Result<Vec<Self>> {
+        match routes.get(model_string) {
+            Some(targets) if !targets.is_empty() => {
+                targets.iter().map(|target| Self::parse(target)).collect()
+            }
+            _ => Ok(vec![Self::parse(model_string)?]),
+        }
+    }
 }
 
 impl std::fmt::Display for ModelRoute {
@@ -103,4 +117,34 @@ mod tests {
         let with_variant = ModelRoute::parse("azure/gpt-4/deployment").unwrap();
         assert_eq!(format!("{}", with_variant), "azure/gpt-4/deployment");
     }
+
+    #[test]
+    fn test_parse_chain_falls_back_to_single_target() {
+        let routes = HashMap::new();
+        let chain = ModelRoute::parse_chain("openai/gpt-4", &routes).unwrap();
+        assert_eq!(chain, vec![ModelRoute::parse("openai/gpt-4").unwrap()]);
+    }
+
+    #[test]
+    fn test_parse_chain_expands_configured_alias() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "best-gpt4".to_string(),
+            vec![
+                "openai/gpt-4o".to_string(),
+                "azure/gpt-4o".to_string(),
+                "openrouter/openai/gpt-4o".to_string(),
+            ],
+        );
+
+        let chain = ModelRoute::parse_chain("best-gpt4", &routes).unwrap();
+        assert_eq!(
+            chain,
+            vec![
+                ModelRoute::parse("openai/gpt-4o").unwrap(),
+                ModelRoute::parse("azure/gpt-4o").unwrap(),
+                ModelRoute::parse("openrouter/openai/gpt-4o").unwrap(),
+            ]
+        );
+    }
 }
@@ -0,0 +1,7 @@
+//! Server Module
+//!
+//! Built-in OpenAI-compatible proxy server.
+
+pub mod proxy;
+
+pub use proxy::serve;
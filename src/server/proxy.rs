@@ -0,0 +1,130 @@
+//! OpenAI-Compatible Proxy Server
+//!
+//! Binds a local socket and exposes a `/v1/chat/completions` endpoint,
+//! forwarding requests through the existing `LlmClient` routing, key-pool
+//! rotation, and retry/backoff. Any client built against the OpenAI SDK can
+//! point at this address and transparently get multi-provider routing
+//! without changing its app code.
+
+use crate::api::streaming::StreamAccumulator;
+use crate::api::CompletionRequest;
+use crate::config::ServerConfig;
+use crate::error::LlmaoError;
+use crate::LlmClient;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[derive(Clone)]
+struct ServerState {
+    client: Arc<LlmClient>,
+    log_completions: bool,
+}
+
+/// Bind `config.host:config.port` and serve the OpenAI-compatible API until
+/// the process is killed or the returned future is dropped.
+pub async fn serve(client: Arc<LlmClient>, config: &ServerConfig) -> crate::error::Result<()> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port)
+        .parse()
+        .map_err(|e| LlmaoError::Config(format!("Invalid server address '{}:{}': {}", config.host, config.port, e)))?;
+
+    let state = ServerState {
+        client,
+        log_completions: config.log_completions,
+    };
+
+    let app = Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/metrics", get(pool_metrics))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| LlmaoError::Internal(format!("Failed to bind {}: {}", addr, e)))?;
+
+    axum::serve(listener, app)
+        .await
+        .map_err(|e| LlmaoError::Internal(format!("Proxy server error: {}", e)))
+}
+
+async fn chat_completions(State(state): State<ServerState>, Json(request): Json<CompletionRequest>) -> Response {
+    if request.stream == Some(true) {
+        stream_chat_completion(state, request).await.into_response()
+    } else {
+        let model = request.model.clone();
+        match state.client.completion(&model, request).await {
+            Ok(response) => Json(response).into_response(),
+            Err(e) => error_response(e),
+        }
+    }
+}
+
+/// Reuse `LlmClient::completion_stream` and re-emit each upstream
+/// `StreamChunk` as an SSE `data:` line, ending with the terminal
+/// `data: [DONE]` the OpenAI API sends. Also feeds every chunk through a
+/// `StreamAccumulator` so the fully reassembled message can be logged once
+/// the stream ends, if `log_completions` is enabled.
+async fn stream_chat_completion(state: ServerState, request: CompletionRequest) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    let model = request.model.clone();
+    let log_completions = state.log_completions;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let _ = state.client.completion_stream(&model, request, tx).await;
+    });
+
+    let stream = async_stream::stream! {
+        let mut accumulator = StreamAccumulator::new();
+
+        while let Some(result) = rx.recv().await {
+            match result {
+                Ok(chunk) => {
+                    let _ = accumulator.process_chunk(&chunk);
+                    if let Ok(data) = serde_json::to_string(&chunk) {
+                        yield Ok(Event::default().data(data));
+                    }
+                }
+                Err(e) => {
+                    yield Ok(Event::default().event("error").data(e.to_string()));
+                }
+            }
+        }
+
+        if log_completions {
+            let message = accumulator.into_message();
+            eprintln!("[llmao proxy] completed: {}", message.content.to_string_content());
+        }
+
+        yield Ok(Event::default().data("[DONE]"));
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Prometheus-text snapshot of every provider's key pool, for operators to
+/// scrape pool health the way they would an admin metrics endpoint.
+async fn pool_metrics(State(state): State<ServerState>) -> String {
+    state.client.pool_metrics()
+}
+
+/// Map an `LlmaoError` to an OpenAI-style `{"error": {"message": ...}}`
+/// body with a roughly corresponding HTTP status.
+fn error_response(err: LlmaoError) -> Response {
+    let status = match &err {
+        LlmaoError::Auth(_) => StatusCode::UNAUTHORIZED,
+        LlmaoError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        LlmaoError::Config(_) | LlmaoError::ProviderNotFound(_) | LlmaoError::ModelNotSupported { .. } => {
+            StatusCode::BAD_REQUEST
+        }
+        LlmaoError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(serde_json::json!({ "error": { "message": err.to_string() } }))).into_response()
+}
@@ -0,0 +1,189 @@
+//! Token Counting
+//!
+//! Estimates prompt size in tokens using BPE encoders, so callers can bound
+//! context size before sending a request.
+
+use crate::api::{CompletionRequest, Message, MessageContent};
+use crate::error::{LlmaoError, Result};
+use tiktoken_rs::CoreBPE;
+
+/// Per-message structural overhead charged by OpenAI-style chat APIs
+const TOKENS_PER_MESSAGE: usize = 3;
+
+/// Priming tokens added for the model's reply
+const TOKENS_PER_REPLY: usize = 3;
+
+/// Selects which BPE vocabulary to tokenize with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// cl100k_base - used by gpt-3.5-turbo/gpt-4 and the default fallback
+    #[default]
+    Cl100kBase,
+
+    /// o200k_base - used by gpt-4o family models
+    O200kBase,
+
+    /// p50k_base - used by older completion-style models
+    P50kBase,
+}
+
+impl Encoding {
+    /// Parse an encoding name as found in provider config (e.g. "cl100k_base")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cl100k_base" => Some(Self::Cl100kBase),
+            "o200k_base" => Some(Self::O200kBase),
+            "p50k_base" => Some(Self::P50kBase),
+            _ => None,
+        }
+    }
+
+    fn bpe(self) -> Result<CoreBPE> {
+        let result = match self {
+            Encoding::Cl100kBase => tiktoken_rs::cl100k_base(),
+            Encoding::O200kBase => tiktoken_rs::o200k_base(),
+            Encoding::P50kBase => tiktoken_rs::p50k_base(),
+        };
+
+        result.map_err(|e| LlmaoError::Internal(format!("Failed to load BPE encoding: {}", e)))
+    }
+}
+
+/// Count the text content of a message, ignoring non-text parts (e.g. images)
+fn message_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::Text(s) => s.clone(),
+        MessageContent::Parts(_) => content.to_string_content(),
+    }
+}
+
+fn count_message_tokens(bpe: &CoreBPE, message: &Message) -> usize {
+    let mut tokens = TOKENS_PER_MESSAGE;
+
+    tokens += bpe.encode_with_special_tokens(&message.role).len();
+    tokens += bpe.encode_with_special_tokens(&message_text(&message.content)).len();
+
+    if let Some(name) = &message.name {
+        tokens += bpe.encode_with_special_tokens(name).len();
+    }
+
+    if let Some(tool_calls) = &message.tool_calls {
+        for call in tool_calls {
+            tokens += bpe.encode_with_special_tokens(&call.function.name).len();
+            tokens += bpe.encode_with_special_tokens(&call.function.arguments).len();
+        }
+    }
+
+    tokens
+}
+
+/// Count the tokens a `CompletionRequest` will cost using the default encoding
+pub fn count_tokens(request: &CompletionRequest) -> usize {
+    count_tokens_with_encoding(request, Encoding::default())
+}
+
+/// Count the tokens a `CompletionRequest` will cost using a specific encoding
+pub fn count_tokens_with_encoding(request: &CompletionRequest, encoding: Encoding) -> usize {
+    let bpe = match encoding.bpe() {
+        Ok(bpe) => bpe,
+        Err(_) => return 0,
+    };
+
+    let mut total = TOKENS_PER_REPLY;
+    for message in &request.messages {
+        total += count_message_tokens(&bpe, message);
+    }
+
+    total
+}
+
+impl CompletionRequest {
+    /// Drop the oldest non-system messages, preserving any leading system
+    /// message and the most recent user turn, until the prompt fits within
+    /// `max_prompt_tokens` (using the default encoding).
+    pub fn truncate_to_budget(&mut self, max_prompt_tokens: usize) {
+        self.truncate_to_budget_with_encoding(max_prompt_tokens, Encoding::default());
+    }
+
+    /// Same as [`truncate_to_budget`](Self::truncate_to_budget), but with an
+    /// explicit encoding (e.g. one resolved from the target provider/model).
+    pub fn truncate_to_budget_with_encoding(&mut self, max_prompt_tokens: usize, encoding: Encoding) {
+        // The most recent `user` turn by role, not whatever happens to sit
+        // last positionally -- a trailing assistant/tool message shouldn't
+        // leave the actual last user turn unprotected.
+        let mut protected_index = self.messages.iter().rposition(|m| m.role == "user");
+
+        while count_tokens_with_encoding(self, encoding) > max_prompt_tokens {
+            let drop_index = self
+                .messages
+                .iter()
+                .enumerate()
+                .position(|(i, m)| Some(i) != protected_index && m.role != "system");
+
+            match drop_index {
+                Some(index) => {
+                    self.messages.remove(index);
+                    if let Some(protected) = protected_index {
+                        if index < protected {
+                            protected_index = Some(protected - 1);
+                        }
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, text: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: MessageContent::Text(text.to_string()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    #[test]
+    fn test_truncate_protects_last_user_turn_even_behind_a_trailing_assistant_message() {
+        let mut request = CompletionRequest::new(
+            "test-model".to_string(),
+            vec![
+                message("system", "Be concise."),
+                message("user", "first question that will get dropped to make room"),
+                message("assistant", "first answer"),
+                message("user", "the most recent user turn, which must survive"),
+                message("assistant", "trailing assistant message sitting last positionally"),
+            ],
+        );
+
+        request.truncate_to_budget_with_encoding(1, Encoding::default());
+
+        assert!(request.messages.iter().any(|m| m.role == "system"));
+        assert!(request
+            .messages
+            .iter()
+            .any(|m| m.content.to_string_content() == "the most recent user turn, which must survive"));
+        assert!(!request
+            .messages
+            .iter()
+            .any(|m| m.content.to_string_content() == "first question that will get dropped to make room"));
+    }
+
+    #[test]
+    fn test_truncate_is_a_noop_when_already_under_budget() {
+        let mut request = CompletionRequest::new(
+            "test-model".to_string(),
+            vec![message("user", "short")],
+        );
+
+        request.truncate_to_budget_with_encoding(1_000, Encoding::default());
+
+        assert_eq!(request.messages.len(), 1);
+    }
+}
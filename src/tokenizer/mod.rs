@@ -0,0 +1,7 @@
+//! Tokenizer Module
+//!
+//! BPE-based token counting and prompt truncation.
+
+pub mod encoding;
+
+pub use encoding::{count_tokens, count_tokens_with_encoding, Encoding};
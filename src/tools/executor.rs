@@ -0,0 +1,416 @@
+//! Tool Execution Loop
+//!
+//! Drives a multi-step tool-calling conversation: send a request, run any
+//! requested tool calls through a registered handler, append the results,
+//! and resend until the model stops asking for tools.
+
+use crate::api::{CompletionRequest, CompletionResponse, Message, MessageContent, ToolCall};
+use crate::error::{LlmaoError, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// A tool handler: takes the parsed JSON arguments and returns the result as a string.
+pub type ToolHandler =
+    Arc<dyn Fn(serde_json::Value) -> Pin<Box<dyn Future<Output = Result<String>> + Send>> + Send + Sync>;
+
+/// Callback consulted before running a `may_`-prefixed handler. Returns `true` to approve.
+pub type ConfirmCallback = Arc<dyn Fn(&str, &serde_json::Value) -> bool + Send + Sync>;
+
+/// Prefix marking a handler as requiring user confirmation before execution.
+const CONFIRMATION_PREFIX: &str = "may_";
+
+/// Bounds how many tool calls within a single turn run at once. Handlers
+/// (especially Python callbacks, which hold the GIL for the call's
+/// duration) are plain synchronous work, so letting an unbounded number
+/// run concurrently would oversubscribe the host; this caps it, defaulting
+/// to the number of logical CPUs.
+#[derive(Clone)]
+pub struct ToolWorkerPool {
+    permits: Arc<Semaphore>,
+}
+
+impl ToolWorkerPool {
+    /// Create a pool sized to the host's logical CPU count.
+    pub fn new() -> Self {
+        Self::with_workers(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+    }
+
+    /// Create a pool with an explicit worker count.
+    pub fn with_workers(workers: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+        }
+    }
+}
+
+impl Default for ToolWorkerPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Registry of tool handlers plus the loop that drives a tool-calling conversation.
+pub struct ToolExecutor {
+    /// Handlers keyed by function name
+    handlers: HashMap<String, ToolHandler>,
+
+    /// Callback to approve/deny `may_`-prefixed handlers
+    confirm: Option<ConfirmCallback>,
+
+    /// Maximum number of request/response round-trips before giving up
+    max_steps: usize,
+
+    /// Caps how many tool calls within a turn run concurrently
+    worker_pool: ToolWorkerPool,
+}
+
+impl ToolExecutor {
+    /// Create a new executor with the given step limit
+    pub fn new(max_steps: usize) -> Self {
+        Self {
+            handlers: HashMap::new(),
+            confirm: None,
+            max_steps,
+            worker_pool: ToolWorkerPool::new(),
+        }
+    }
+
+    /// Register a handler for a function name
+    pub fn register(&mut self, name: impl Into<String>, handler: ToolHandler) {
+        self.handlers.insert(name.into(), handler);
+    }
+
+    /// Set the callback used to approve/deny `may_`-prefixed handlers
+    pub fn with_confirm_callback(mut self, callback: ConfirmCallback) -> Self {
+        self.confirm = Some(callback);
+        self
+    }
+
+    /// Override the default (num-CPUs-sized) concurrency cap for tool calls
+    /// within a single turn.
+    pub fn with_worker_pool(mut self, worker_pool: ToolWorkerPool) -> Self {
+        self.worker_pool = worker_pool;
+        self
+    }
+
+    /// Whether a handler name requires confirmation before execution
+    fn requires_confirmation(name: &str) -> bool {
+        name.starts_with(CONFIRMATION_PREFIX)
+    }
+
+    /// Drive the tool-calling loop to completion.
+    ///
+    /// `send` performs one request/response round-trip (typically
+    /// `LlmClient::completion`). Tool calls returned by each response are
+    /// executed and appended to `request.messages` before resending.
+    pub async fn run<F, Fut>(
+        &self,
+        request: CompletionRequest,
+        send: F,
+    ) -> Result<CompletionResponse>
+    where
+        F: FnMut(CompletionRequest) -> Fut,
+        Fut: Future<Output = Result<CompletionResponse>>,
+    {
+        self.run_with_trace(request, send).await.map(|(response, _)| response)
+    }
+
+    /// Like [`Self::run`], but also returns the full conversation trace:
+    /// the request's original messages followed by every assistant/tool
+    /// message appended while driving the loop, ending with the final
+    /// assistant answer. Useful for callers that
+    /// want to inspect or persist the intermediate tool-calling steps
+    /// rather than just the final response.
+    pub async fn run_with_trace<F, Fut>(
+        &self,
+        mut request: CompletionRequest,
+        mut send: F,
+    ) -> Result<(CompletionResponse, Vec<Message>)>
+    where
+        F: FnMut(CompletionRequest) -> Fut,
+        Fut: Future<Output = Result<CompletionResponse>>,
+    {
+        for _ in 0..self.max_steps {
+            let response = send(request.clone()).await?;
+
+            let choice = match response.choices.first() {
+                Some(choice) => choice,
+                None => return Ok((response, request.messages)),
+            };
+
+            let has_tool_calls = choice.message.tool_calls.as_ref().is_some_and(|tc| !tc.is_empty());
+            let finished_on_tools = choice.finish_reason.as_deref() == Some("tool_calls");
+
+            if !has_tool_calls && !finished_on_tools {
+                request.messages.push(choice.message.clone());
+                return Ok((response, request.messages));
+            }
+
+            let tool_calls = match &choice.message.tool_calls {
+                Some(tool_calls) if !tool_calls.is_empty() => tool_calls.clone(),
+                _ => {
+                    request.messages.push(choice.message.clone());
+                    return Ok((response, request.messages));
+                }
+            };
+
+            // Append the assistant turn that requested the tool calls
+            request.messages.push(Message {
+                role: "assistant".to_string(),
+                content: choice.message.content.clone(),
+                name: None,
+                tool_calls: Some(tool_calls.clone()),
+                tool_call_id: None,
+            });
+
+            // Execute all calls in this turn concurrently (bounded by
+            // `worker_pool`) instead of one at a time, so an independent,
+            // slow tool call doesn't hold up the rest; results are
+            // collected back in the original call order before resending.
+            let mut join_set = tokio::task::JoinSet::new();
+            for (position, call) in tool_calls.iter().cloned().enumerate() {
+                let handlers = self.handlers.clone();
+                let confirm = self.confirm.clone();
+                let permits = self.worker_pool.permits.clone();
+                join_set.spawn(async move {
+                    let _permit = permits.acquire_owned().await.expect("worker pool semaphore is never closed");
+                    let result = Self::execute_with(&handlers, &confirm, &call).await;
+                    (position, call.id, result)
+                });
+            }
+
+            let mut results: Vec<Option<(String, Result<String>)>> = (0..tool_calls.len()).map(|_| None).collect();
+            while let Some(joined) = join_set.join_next().await {
+                let (position, call_id, result) = joined
+                    .map_err(|e| LlmaoError::Internal(format!("tool worker task panicked: {}", e)))?;
+                results[position] = Some((call_id, result));
+            }
+
+            for entry in results {
+                let (call_id, result) = entry.expect("every spawned position is filled exactly once");
+                let content = result.map_err(|e| LlmaoError::ToolCallFailed {
+                    tool_call_id: call_id.clone(),
+                    message: e.to_string(),
+                })?;
+
+                request.messages.push(Message {
+                    role: "tool".to_string(),
+                    content: MessageContent::Text(content),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: Some(call_id),
+                });
+            }
+        }
+
+        Err(LlmaoError::Internal(format!(
+            "tool execution loop exceeded max_steps ({})",
+            self.max_steps
+        )))
+    }
+
+    /// Execute a single tool call, enforcing the confirmation gate if
+    /// needed. Takes the handler registry and confirm callback by
+    /// reference rather than `&self` so it can run inside a
+    /// `tokio::spawn`ed ('static) task built from cloned `Arc`s.
+    async fn execute_with(
+        handlers: &HashMap<String, ToolHandler>,
+        confirm: &Option<ConfirmCallback>,
+        call: &ToolCall,
+    ) -> Result<String> {
+        let name = &call.function.name;
+
+        let handler = handlers.get(name).ok_or_else(|| LlmaoError::ToolNotFound(name.clone()))?;
+
+        let args: serde_json::Value = serde_json::from_str(&call.function.arguments)
+            .map_err(|e| LlmaoError::Response(format!("Invalid tool arguments for '{}': {}", name, e)))?;
+
+        if Self::requires_confirmation(name) {
+            let approved = match confirm {
+                Some(callback) => callback(name, &args),
+                None => false,
+            };
+            if !approved {
+                return Err(LlmaoError::ToolExecutionDenied(name.clone()));
+            }
+        }
+
+        handler(args).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Choice, CompletionResponse, FunctionCall};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn tool_call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+            complete: None,
+        }
+    }
+
+    fn assistant_message_with_calls(calls: Vec<ToolCall>) -> Message {
+        Message {
+            role: "assistant".to_string(),
+            content: MessageContent::Text(String::new()),
+            name: None,
+            tool_calls: Some(calls),
+            tool_call_id: None,
+        }
+    }
+
+    fn response_with_choice(message: Message, finish_reason: Option<&str>) -> CompletionResponse {
+        CompletionResponse {
+            id: "resp".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "test-model".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: finish_reason.map(str::to_string),
+            }],
+            usage: None,
+        }
+    }
+
+    fn final_response(text: &str) -> CompletionResponse {
+        response_with_choice(
+            Message {
+                role: "assistant".to_string(),
+                content: MessageContent::Text(text.to_string()),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            },
+            Some("stop"),
+        )
+    }
+
+    fn echo_handler() -> ToolHandler {
+        Arc::new(|args| Box::pin(async move { Ok(args.to_string()) }))
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_tool_calls_preserve_order_in_appended_messages() {
+        let mut executor = ToolExecutor::new(5);
+        executor.register(
+            "slow",
+            Arc::new(|args| {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok(args.to_string())
+                })
+            }),
+        );
+        executor.register("fast", echo_handler());
+        // Force real concurrency regardless of how many cores the sandbox
+        // reports, so "fast" actually finishes before the "slow" calls do.
+        let executor = executor.with_worker_pool(ToolWorkerPool::with_workers(4));
+
+        let calls = vec![
+            tool_call("call-1", "slow", r#"{"v":1}"#),
+            tool_call("call-2", "fast", r#"{"v":2}"#),
+            tool_call("call-3", "slow", r#"{"v":3}"#),
+        ];
+        let tool_response = response_with_choice(assistant_message_with_calls(calls), Some("tool_calls"));
+
+        let step = AtomicUsize::new(0);
+        let request = CompletionRequest::new("test-model".to_string(), vec![]);
+        let (_, trace) = executor
+            .run_with_trace(request, move |_req| {
+                let n = step.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if n == 0 {
+                        Ok(tool_response.clone())
+                    } else {
+                        Ok(final_response("done"))
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        // Even though "slow" calls finish after "fast", the tool results must
+        // land back in the original call order, not completion order.
+        let tool_messages: Vec<&Message> = trace.iter().filter(|m| m.role == "tool").collect();
+        assert_eq!(tool_messages.len(), 3);
+        assert_eq!(tool_messages[0].tool_call_id.as_deref(), Some("call-1"));
+        assert_eq!(tool_messages[1].tool_call_id.as_deref(), Some("call-2"));
+        assert_eq!(tool_messages[2].tool_call_id.as_deref(), Some("call-3"));
+    }
+
+    #[tokio::test]
+    async fn test_denied_confirmation_call_surfaces_tool_execution_denied() {
+        let mut executor = ToolExecutor::new(3);
+        executor.register("may_delete", echo_handler());
+        let executor = executor.with_confirm_callback(Arc::new(|_name, _args| false));
+
+        let calls = vec![tool_call("call-1", "may_delete", "{}")];
+        let tool_response = response_with_choice(assistant_message_with_calls(calls), Some("tool_calls"));
+
+        let request = CompletionRequest::new("test-model".to_string(), vec![]);
+        let err = executor
+            .run(request, move |_req| {
+                let tool_response = tool_response.clone();
+                async move { Ok(tool_response) }
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, LlmaoError::ToolExecutionDenied(name) if name == "may_delete"));
+    }
+
+    #[tokio::test]
+    async fn test_failing_call_surfaces_tool_call_failed_without_hanging_other_workers() {
+        let mut executor = ToolExecutor::new(3);
+        executor.register(
+            "failing",
+            Arc::new(|_args| Box::pin(async move { Err(LlmaoError::Response("boom".to_string())) })),
+        );
+        executor.register(
+            "slow_ok",
+            Arc::new(|args| {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok(args.to_string())
+                })
+            }),
+        );
+
+        let calls = vec![
+            tool_call("call-1", "failing", "{}"),
+            tool_call("call-2", "slow_ok", "{}"),
+        ];
+        let tool_response = response_with_choice(assistant_message_with_calls(calls), Some("tool_calls"));
+
+        let request = CompletionRequest::new("test-model".to_string(), vec![]);
+        let err = tokio::time::timeout(
+            Duration::from_secs(1),
+            executor.run(request, move |_req| {
+                let tool_response = tool_response.clone();
+                async move { Ok(tool_response) }
+            }),
+        )
+        .await
+        .expect("tool loop hung instead of returning once a worker failed")
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            LlmaoError::ToolCallFailed { tool_call_id, .. } if tool_call_id == "call-1"
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+//! Tools Module
+//!
+//! Multi-step tool/function-calling execution loop.
+
+pub mod executor;
+
+pub use executor::{ConfirmCallback, ToolExecutor, ToolHandler, ToolWorkerPool};